@@ -1,9 +1,11 @@
-use crate::registry::Rule;
+use crate::diagnostic::Lang;
+use crate::encoding::parse_label;
+use crate::registry::{self, Group, Rule};
 use clap::builder::{PossibleValue, TypedValueParser};
 use clap::{Parser, Subcommand, command};
 use clap_complete::Shell;
+use encoding_rs::Encoding;
 use std::path::PathBuf;
-use strum::IntoEnumIterator;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -19,7 +21,7 @@ pub struct Args {
 #[derive(Debug, Subcommand)]
 pub enum Command {
     /// Run Grs on the given text files
-    Check(CheckCommand),
+    Check(Box<CheckCommand>),
 
     /// Convert text to monotonic Greek
     ToMonotonic {
@@ -28,6 +30,52 @@ pub enum Command {
         files: Vec<PathBuf>,
     },
 
+    /// Convert text files from one encoding to another, in place
+    ConvertEncoding {
+        /// Encoding of the files. Detected if not given.
+        #[arg(long, value_parser = parse_label)]
+        from: Option<&'static Encoding>,
+
+        /// Encoding to convert to.
+        #[arg(long, value_parser = parse_label, default_value = "utf-8")]
+        to: &'static Encoding,
+
+        /// Files to convert.
+        #[arg(value_parser, required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Run the language server, over stdio
+    Server,
+
+    /// Serve a LanguageTool compatible HTTP API
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8081)]
+        port: u16,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+
+    /// Speak the ispell pipe protocol over stdio, for Emacs, Vim or mutt
+    Pipe {
+        /// Pipe mode, as in `ispell -a`. Accepted for compatibility.
+        #[arg(short = 'a')]
+        ispell: bool,
+    },
+
+    /// Run as a pandoc JSON filter, reading the AST from stdin
+    PandocFilter {
+        /// Output the AST with the fixes applied.
+        #[arg(long)]
+        fix: bool,
+
+        /// Output format, passed by pandoc. Ignored.
+        format: Option<String>,
+    },
+
     /// Generate shell completions
     GenerateCompletions {
         /// Shell to generate completions for
@@ -39,19 +87,71 @@ pub enum Command {
 // Cf. https://github.com/astral-sh/ruff/blob/1bdb22c13972b3a3dc9cb4ef31fbf37db051dd1c/crates/ruff/src/args.rs#L185
 #[derive(Parser, Debug)]
 pub struct CheckCommand {
-    /// Files to process. Anything other than .txt files will be ignored.
-    #[arg(value_parser, required = true)]
+    /// Files to process (.txt, .md, .tex, .wiki, .po, .json, .yaml, .csv). Other files will be ignored.
+    #[arg(value_parser, required_unless_present_any = ["mediawiki_dump", "staged", "diff_base"])]
     pub files: Vec<PathBuf>,
 
+    /// Only report errors on the lines staged in git, in the staged content of
+    /// the files. Checks all the staged files if no file is given. Can not be
+    /// used with `--fix`, which would only fix the working tree.
+    #[arg(long, conflicts_with_all = ["diff_base", "mediawiki_dump", "fix"])]
+    pub staged: bool,
+
+    /// Only report and fix errors on the lines changed since a git revision.
+    /// Checks all the changed files if no file is given.
+    #[arg(long, value_name = "REV", conflicts_with = "mediawiki_dump")]
+    pub diff_base: Option<String>,
+
+    /// Check the articles of a MediaWiki XML dump (.xml or .xml.bz2).
+    #[arg(long, value_name = "DUMP", conflicts_with_all = ["files", "fix", "diff"])]
+    pub mediawiki_dump: Option<PathBuf>,
+
+    /// Also check the fuzzy entries of PO files.
+    #[arg(long)]
+    pub include_fuzzy: bool,
+
+    /// Only check the values of JSON and YAML files whose key path matches the
+    /// pattern, where `*` matches a part of a key and `**` any number of keys.
+    /// Ex. `/el/errors/**`.
+    #[arg(long, value_name = "PATTERN")]
+    pub keys: Option<String>,
+
+    /// Read the files as CSV (TSV if their extension is .tsv).
+    #[arg(long)]
+    pub csv: bool,
+
+    /// Only check these columns of CSV files, by header name or number.
+    #[arg(long, value_delimiter = ',', value_name = "COLUMN")]
+    pub columns: Option<Vec<String>>,
+
+    /// Encoding of the files, ex. `iso-8859-7` or `cp1253`. Detected if not
+    /// given, among UTF-8, UTF-16 with a byte order mark, ISO-8859-7 and CP1253.
+    /// Fixed files are written back in their encoding.
+    #[arg(long, value_parser = parse_label)]
+    pub encoding: Option<&'static Encoding>,
+
     /// Replace the input file.
     #[arg(long)]
     pub fix: bool,
 
+    /// With `--fix`, keep a copy of the original files, with this suffix
+    /// appended to their name.
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "~",
+        requires = "fix"
+    )]
+    pub backup: Option<String>,
+
     /// Show differences between original and corrected text.
     #[arg(long)]
     pub diff: bool,
 
-    /// Specify which types of mistakes to check.
+    /// Specify which types of mistakes to check, by rule code, code prefix,
+    /// group (accent, char, grammar, typography) or ALL.
     #[arg(
         long,
         value_delimiter = ',',
@@ -61,7 +161,7 @@ pub struct CheckCommand {
     )]
     pub select: Option<Vec<RuleSelector>>,
 
-    /// Specify which types of mistakes to ignore.
+    /// Specify which types of mistakes to ignore, as in `--select`.
     #[arg(
         long,
         value_delimiter = ',',
@@ -71,28 +171,74 @@ pub struct CheckCommand {
     )]
     pub ignore: Option<Vec<RuleSelector>>,
 
+    /// Report the diagnostics of these rules as errors, as in `--select`. Errors
+    /// make the check fail.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "RULE_CODE",
+        value_parser = RuleSelectorParser,
+        hide_possible_values = true,
+    )]
+    pub error: Vec<RuleSelector>,
+
+    /// Report the diagnostics of these rules as warnings, as in `--select`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "RULE_CODE",
+        value_parser = RuleSelectorParser,
+        hide_possible_values = true,
+    )]
+    pub warn: Vec<RuleSelector>,
+
+    /// Report the diagnostics of these rules as information, as in `--select`.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "RULE_CODE",
+        value_parser = RuleSelectorParser,
+        hide_possible_values = true,
+    )]
+    pub info: Vec<RuleSelector>,
+
+    /// Enable the rules in preview, which are new and may change.
+    #[arg(long)]
+    pub preview: bool,
+
+    /// Language of the messages: en or el.
+    #[arg(long, value_name = "LANG")]
+    pub lang: Option<Lang>,
+
     /// Show statistics after processing.
     #[arg(long)]
     pub statistics: bool,
 }
 
-// The whole point of selector is to deal with the --select ALL
-// option in the CLI. While they do it like this in ruff to expand linter
-// groups, that is most likely out of our reach for this project.
+// Selectors of rules, as in ruff: `ALL`, a group (`accent`), a code (`MA`) or
+// a prefix of codes (`F` for `FA`, `FDA` and `FC`).
 //
 // Though it has the advantage of customizing the possible values, printed
 // when one types --select with no extra arguments.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RuleSelector {
     All,
+    Group(Group),
+    Prefix(String),
     Selection(Rule),
+    /// An old code, kept to warn about it, and the rule it now refers to.
+    Redirect(&'static str, Rule),
 }
 
 impl RuleSelector {
     pub fn rules(&self) -> Vec<Rule> {
         match self {
             Self::All => Rule::iter().collect(),
-            Self::Selection(selection) => vec![*selection],
+            Self::Group(group) => group.rules().collect(),
+            Self::Prefix(prefix) => Rule::iter()
+                .filter(|rule| rule.code().starts_with(prefix.as_str()))
+                .collect(),
+            Self::Selection(selection) | Self::Redirect(_, selection) => vec![*selection],
         }
     }
 }
@@ -101,9 +247,22 @@ impl std::str::FromStr for RuleSelector {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "ALL" => Ok(Self::All),
-            _ => Ok(Self::Selection(s.parse()?)),
+        // A code takes precedence over a prefix: `MA` does not select `MAC`.
+        if s == "ALL" {
+            return Ok(Self::All);
+        }
+        if let Ok(group) = s.parse() {
+            return Ok(Self::Group(group));
+        }
+        if let Some((old, rule)) = registry::redirect(s) {
+            return Ok(Self::Redirect(old, rule));
+        }
+        match s.parse() {
+            Ok(rule) => Ok(Self::Selection(rule)),
+            Err(_) if !s.is_empty() && Rule::iter().any(|rule| rule.code().starts_with(s)) => {
+                Ok(Self::Prefix(s.to_string()))
+            }
+            Err(err) => Err(err),
         }
     }
 }
@@ -142,12 +301,13 @@ impl TypedValueParser for RuleSelectorParser {
 
     fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
         Some(Box::new(
-            std::iter::once(PossibleValue::new("ALL").help("all rules")).chain(Rule::iter().map(
-                |rule| {
-                    let code: String = rule.to_string();
-                    PossibleValue::new(code)
-                },
-            )),
+            std::iter::once(PossibleValue::new("ALL").help("all rules"))
+                .chain(
+                    Group::ALL
+                        .into_iter()
+                        .map(|group| PossibleValue::new(group.name()).help("group")),
+                )
+                .chain(Rule::iter().map(|rule| PossibleValue::new(rule.code()))),
         ))
     }
 }
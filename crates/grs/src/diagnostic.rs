@@ -1,12 +1,277 @@
+use std::collections::HashMap;
+
 use crate::range::TextRange;
 use crate::registry::Rule;
 
-// We do not use Edit since our replacement logic is much simpler,
-// usually consisting of modifying solely substrings.
-#[derive(Debug, Clone)]
-pub struct Fix {
-    pub replacement: String,
+/// How serious a diagnostic is. Only errors make `grs check` fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub const ALL: [Self; 3] = [Self::Info, Self::Warning, Self::Error];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.name() == name)
+            .ok_or_else(|| format!("Unknown severity: {name}"))
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Severities of rules, overriding their default one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Severities(HashMap<Rule, Severity>);
+
+impl Severities {
+    pub fn get(&self, rule: Rule) -> Severity {
+        self.0
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.severity())
+    }
+
+    pub fn set(&mut self, rule: Rule, severity: Severity) {
+        self.0.insert(rule, severity);
+    }
+
+    /// Give diagnostics the severity of their rule.
+    pub fn apply(&self, diagnostics: &mut [Diagnostic]) {
+        for diagnostic in diagnostics {
+            diagnostic.severity = self.get(diagnostic.kind);
+        }
+    }
+}
+
+/// Language of the messages of diagnostics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Lang {
+    #[default]
+    En,
+    El,
+}
+
+impl Lang {
+    pub const ALL: [Self; 2] = [Self::En, Self::El];
+
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::El => "el",
+        }
+    }
+}
+
+impl std::str::FromStr for Lang {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.code() == code)
+            .ok_or_else(|| format!("Unknown language: {code} (expected en or el)"))
+    }
+}
+
+impl std::fmt::Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// How diagnostics are reported: their severity and the language of their
+/// message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportOptions {
+    pub severities: Severities,
+    pub lang: Lang,
+}
+
+/// What is wrong, ex. `final -ν should be omitted before «δ»`, in Greek and
+/// in English.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    el: String,
+    en: String,
+}
+
+impl Message {
+    pub const fn new(el: String, en: String) -> Self {
+        Self { el, en }
+    }
+
+    pub fn get(&self, lang: Lang) -> &str {
+        match lang {
+            Lang::El => &self.el,
+            Lang::En => &self.en,
+        }
+    }
+}
+
+/// A change of the text: an insertion if `range` is empty, a deletion if
+/// `content` is empty, else a replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
     pub range: TextRange,
+    pub content: String,
+}
+
+impl Edit {
+    pub const fn replacement(content: String, range: TextRange) -> Self {
+        Self { range, content }
+    }
+
+    pub const fn insertion(content: String, offset: usize) -> Self {
+        Self {
+            range: TextRange::new(offset, offset),
+            content,
+        }
+    }
+
+    pub const fn deletion(range: TextRange) -> Self {
+        Self {
+            range,
+            content: String::new(),
+        }
+    }
+
+    /// The smallest edit turning `old`, found at `offset`, into `new`, ex. only
+    /// the accented vowel of a word.
+    pub fn diff(offset: usize, old: &str, new: &str) -> Self {
+        let prefix: usize = old
+            .chars()
+            .zip(new.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        let suffix: usize = old[prefix..]
+            .chars()
+            .rev()
+            .zip(new[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        Self {
+            range: TextRange::new(offset + prefix, offset + old.len() - suffix),
+            content: new[prefix..new.len() - suffix].to_string(),
+        }
+    }
+}
+
+/// Edits applied together, or not at all.
+///
+/// The edits are sorted and do not overlap, though an insertion may touch the
+/// range of another edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    edits: Vec<Edit>,
+}
+
+impl Fix {
+    /// # Panics
+    ///
+    /// Panics if there are no edits or if two of them overlap.
+    pub fn new(mut edits: Vec<Edit>) -> Self {
+        assert!(!edits.is_empty(), "a fix needs at least one edit");
+        edits.sort_by_key(|edit| (edit.range.start(), edit.range.end()));
+        assert!(
+            edits
+                .windows(2)
+                .all(|pair| pair[0].range.end() <= pair[1].range.start()),
+            "overlapping edits: {edits:?}"
+        );
+        Self { edits }
+    }
+
+    pub fn edit(edit: Edit) -> Self {
+        Self { edits: vec![edit] }
+    }
+
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// The range covered by the edits, from the start of the first one to the
+    /// end of the last one.
+    pub fn range(&self) -> TextRange {
+        let first = self.edits.first().expect("a fix has edits");
+        let last = self.edits.last().expect("a fix has edits");
+        TextRange::new(first.range.start(), last.range.end())
+    }
+
+    /// The new content of [`Fix::range`] in `text`, the text the fix was made
+    /// for. For clients that take a single replacement.
+    pub fn replacement(&self, text: &str) -> String {
+        let mut replacement = String::new();
+        let mut last = self.range().start();
+        for edit in &self.edits {
+            replacement.push_str(&text[last..edit.range.start()]);
+            replacement.push_str(&edit.content);
+            last = edit.range.end();
+        }
+        replacement
+    }
+
+    /// `text` with the fix applied.
+    pub fn apply(&self, text: &str) -> String {
+        let range = self.range();
+        format!(
+            "{}{}{}",
+            &text[..range.start()],
+            self.replacement(text),
+            &text[range.end()..]
+        )
+    }
+}
+
+/// A fix offered for a diagnostic, with a label for editors, ex. `replace ς
+/// with σ`.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub label: Message,
+    pub fix: Fix,
+    /// Whether `--fix` applies it. A diagnostic has at most one preferred
+    /// suggestion, and none if no fix is safe to apply blindly.
+    pub preferred: bool,
+}
+
+impl Suggestion {
+    pub const fn preferred(label: Message, fix: Fix) -> Self {
+        Self {
+            label,
+            fix,
+            preferred: true,
+        }
+    }
+
+    pub const fn alternative(label: Message, fix: Fix) -> Self {
+        Self {
+            label,
+            fix,
+            preferred: false,
+        }
+    }
 }
 
 // Simplified version of:
@@ -19,7 +284,62 @@ pub struct Diagnostic {
     /// Range of the diagnostic.
     ///
     /// Only used to visualize the diagnostic, as opposed to [`Fix::range`],
-    /// which, when there is a fix, is used for actual string replacement.
+    /// which, when there is a suggestion, is used for actual string replacement.
     pub range: TextRange,
-    pub fix: Option<Fix>,
+    /// Fixes to offer, best first.
+    pub suggestions: Vec<Suggestion>,
+    /// The default severity of the rule, unless overridden by the config.
+    pub severity: Severity,
+    pub message: Message,
+}
+
+impl Diagnostic {
+    /// The fix of the preferred suggestion, applied by `--fix`.
+    pub fn fix(&self) -> Option<&Fix> {
+        self.suggestions
+            .iter()
+            .find(|suggestion| suggestion.preferred)
+            .map(|suggestion| &suggestion.fix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        // Only the accented vowel.
+        let edit = Edit::diff(4, "πώς", "πως");
+        assert_eq!(edit.range, TextRange::new(6, 8));
+        assert_eq!(edit.content, "ω");
+        // Only the final ν.
+        let edit = Edit::diff(0, "στην", "στη");
+        assert_eq!(edit, Edit::deletion(TextRange::new(6, 8)));
+    }
+
+    #[test]
+    fn test_fix() {
+        let text = "Aλλά το το";
+        let fix = Fix::new(vec![
+            Edit::deletion(TextRange::new(12, 17)),
+            Edit::replacement("Α".to_string(), TextRange::new(0, 1)),
+        ]);
+        assert_eq!(fix.range(), TextRange::new(0, 17));
+        assert_eq!(fix.apply(text), "Αλλά το");
+        assert_eq!(fix.replacement(text), "Αλλά το");
+
+        let fix = Fix::edit(Edit::insertion(",".to_string(), 12));
+        assert_eq!(fix.apply(text), "Aλλά το, το");
+        assert_eq!(fix.replacement(text), ",");
+    }
+
+    #[test]
+    #[should_panic = "overlapping edits"]
+    fn test_overlapping_edits() {
+        Fix::new(vec![
+            Edit::deletion(TextRange::new(0, 4)),
+            Edit::deletion(TextRange::new(2, 6)),
+        ]);
+    }
 }
@@ -0,0 +1,211 @@
+//! Text encodings of input files.
+//!
+//! Besides UTF-8, Greek texts are often found in ISO-8859-7 or Windows-1253
+//! (CP1253), and sometimes in UTF-16. Files are decoded for linting and written
+//! back in their original encoding.
+use encoding_rs::{Encoding, ISO_8859_7, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1253};
+
+use crate::formats::line_col;
+
+/// The encoding of a file, and whether it starts with a byte order mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEncoding {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+impl FileEncoding {
+    pub const UTF_8: Self = Self {
+        encoding: UTF_8,
+        bom: false,
+    };
+
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+}
+
+/// Parse an encoding label, ex. `iso-8859-7`, `cp1253` or `utf-16`.
+///
+/// # Errors
+///
+/// Returns an error message if the label is unknown.
+pub fn parse_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| format!("unknown encoding: {label}"))
+}
+
+/// Guess the legacy Greek encoding of `bytes`, which are not UTF-8.
+///
+/// The two differ on 0x80-0x9F, control characters in ISO-8859-7 but quotes,
+/// dashes etc. in CP1253, and on the capital alpha with tonos, at 0xB6 in
+/// ISO-8859-7 and 0xA2 in CP1253. 0xA2 is also the apostrophe of ISO-8859-7,
+/// and 0xB6 the pilcrow of CP1253: they only count as an alpha at the start of
+/// a word, before a lowercase letter.
+fn guess_legacy(bytes: &[u8]) -> &'static Encoding {
+    // Letters are at 0xB8-0xFE, lowercase ones from 0xDC, in both encodings.
+    let is_letter = |b: u8| b.is_ascii_alphabetic() || (0xB8..=0xFE).contains(&b);
+    let is_lowercase = |b: u8| (0xDC..=0xFE).contains(&b);
+    let capital_alphas = |byte: u8| {
+        (0..bytes.len())
+            .filter(|&idx| {
+                bytes[idx] == byte
+                    && bytes.get(idx + 1).is_some_and(|&next| is_lowercase(next))
+                    && (idx == 0 || !is_letter(bytes[idx - 1]))
+            })
+            .count()
+    };
+    if bytes.iter().any(|b| (0x80..=0x9F).contains(b))
+        || capital_alphas(0xA2) > capital_alphas(0xB6)
+    {
+        WINDOWS_1253
+    } else {
+        ISO_8859_7
+    }
+}
+
+/// Detect the encoding of `bytes`: UTF-8 or UTF-16 if there is a byte order
+/// mark, else UTF-8 if valid, else ISO-8859-7 or CP1253.
+pub fn detect(bytes: &[u8]) -> FileEncoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return FileEncoding {
+            encoding,
+            bom: true,
+        };
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return FileEncoding::UTF_8;
+    }
+    FileEncoding {
+        encoding: guess_legacy(bytes),
+        bom: false,
+    }
+}
+
+/// Decode `bytes`, in `encoding` if given, else in the detected encoding.
+///
+/// # Errors
+///
+/// Returns an error message if `bytes` are not valid in the encoding.
+pub fn decode(
+    bytes: &[u8],
+    encoding: Option<&'static Encoding>,
+) -> Result<(String, FileEncoding), String> {
+    let detected = detect(bytes);
+    let file_encoding = match encoding {
+        // A BOM is kept only if it is the one of the given encoding.
+        Some(encoding) => FileEncoding {
+            encoding,
+            bom: detected.bom && detected.encoding == encoding,
+        },
+        None => detected,
+    };
+    let bom_len = if file_encoding.bom {
+        Encoding::for_bom(bytes).map_or(0, |(_, len)| len)
+    } else {
+        0
+    };
+    let text = file_encoding
+        .encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes[bom_len..])
+        .ok_or_else(|| format!("invalid {} text", file_encoding.name()))?;
+    Ok((text.into_owned(), file_encoding))
+}
+
+/// Encode `text` in `encoding`.
+///
+/// # Errors
+///
+/// Returns an error message, with its position, if a character of `text` can
+/// not be represented in the encoding.
+pub fn encode(text: &str, file_encoding: FileEncoding) -> Result<Vec<u8>, String> {
+    let encoding = file_encoding.encoding;
+    let mut bytes = Vec::with_capacity(text.len());
+    // encoding_rs only decodes UTF-16.
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let units = std::iter::once('\u{FEFF}')
+            .filter(|_| file_encoding.bom)
+            .chain(text.chars())
+            .collect::<String>();
+        for unit in units.encode_utf16() {
+            if encoding == UTF_16LE {
+                bytes.extend(unit.to_le_bytes());
+            } else {
+                bytes.extend(unit.to_be_bytes());
+            }
+        }
+        return Ok(bytes);
+    }
+
+    if file_encoding.bom {
+        bytes.extend("\u{FEFF}".as_bytes());
+    }
+    let (encoded, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        let mut buf = [0; 4];
+        let (offset, c) = text
+            .char_indices()
+            .find(|(_, c)| encoding.encode(c.encode_utf8(&mut buf)).2)
+            .unwrap_or_default();
+        let (line, col) = line_col(text, offset);
+        return Err(format!(
+            "line {line}, column {col}: {c:?} can not be represented in {}",
+            encoding.name()
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "«Άλλο ένα», είπε.";
+
+    #[test]
+    fn test_roundtrip() {
+        for encoding in [UTF_8, ISO_8859_7, WINDOWS_1253, UTF_16LE, UTF_16BE] {
+            let bom = encoding == UTF_16LE || encoding == UTF_16BE;
+            let file_encoding = FileEncoding { encoding, bom };
+            let bytes = encode(TEXT, file_encoding).unwrap();
+            assert_eq!(
+                decode(&bytes, None).unwrap(),
+                (TEXT.to_string(), file_encoding)
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_legacy() {
+        // Ά is 0xB6 in ISO-8859-7 and 0xA2 in CP1253.
+        assert_eq!(detect(&[0xB6, 0xEB, 0xEB, 0xEF]).encoding, ISO_8859_7);
+        assert_eq!(detect(&[0xA2, 0xEB, 0xEB, 0xEF]).encoding, WINDOWS_1253);
+        // Quotes only exist in CP1253.
+        assert_eq!(detect(&[0x93, 0xE1, 0x94]).encoding, WINDOWS_1253);
+        // Apostrophes are 0xA2 in ISO-8859-7.
+        let (bytes, _, _) = ISO_8859_7.encode("Σ’ αγαπώ, τ’ άκουσα, μ’έλεγε: Άννα.");
+        assert_eq!(detect(&bytes).encoding, ISO_8859_7);
+        let (bytes, _, _) = WINDOWS_1253.encode("Άννα, Άρης και ¶ 3.");
+        assert_eq!(detect(&bytes).encoding, WINDOWS_1253);
+    }
+
+    #[test]
+    fn test_forced() {
+        let bytes = encode("Άλλο", FileEncoding::UTF_8).unwrap();
+        assert!(decode(&bytes, Some(UTF_8)).is_ok());
+        let (text, _) = decode(&bytes, Some(WINDOWS_1253)).unwrap();
+        assert_ne!(text, "Άλλο");
+        assert_eq!(parse_label("cp1253"), Ok(WINDOWS_1253));
+        assert!(parse_label("klingon").is_err());
+    }
+
+    #[test]
+    fn test_unrepresentable() {
+        let file_encoding = FileEncoding {
+            encoding: ISO_8859_7,
+            bom: false,
+        };
+        let err = encode("Καλά\nκαι 😀", file_encoding).unwrap_err();
+        assert!(err.starts_with("line 2, column 5"), "{err}");
+    }
+}
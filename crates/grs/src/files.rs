@@ -0,0 +1,130 @@
+//! Safe in-place writing of fixed files.
+//!
+//! Files are written to a temporary file next to them, which is then renamed
+//! over the original: an interrupted `--fix` leaves either the original or the
+//! fixed file, never a truncated one. Symbolic links are followed, so that the
+//! file they point to is replaced, not the link.
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Give `text` the line endings of `original`: if it only uses CRLF, so do the
+/// lines added by fixes.
+///
+/// The text of an original with mixed line endings is left as it is, since the
+/// fixed lines can not be told from the untouched ones.
+pub fn with_line_endings<'a>(original: &str, text: &'a str) -> Cow<'a, str> {
+    let crlf = original.matches("\r\n").count();
+    if crlf == 0
+        || crlf != original.matches('\n').count()
+        || !text.replace("\r\n", "").contains('\n')
+    {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+}
+
+/// The path of the backup of `path`, ex. `a.txt~` for the suffix `~`.
+pub fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Atomically replace the content of `path` with `bytes`, keeping its
+/// permissions (and on Unix its owner and group, when allowed), and copy the
+/// original to `backup` first, if given.
+///
+/// # Errors
+///
+/// Returns an error if the temporary file can not be written or renamed, in
+/// which case `path` is left untouched.
+pub fn write_atomic(path: &Path, bytes: &[u8], backup: Option<&Path>) -> io::Result<()> {
+    // The target of a symbolic link, with an absolute path.
+    let path = &fs::canonicalize(path)?;
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let metadata = fs::metadata(path)?;
+
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(bytes)?;
+    file.as_file().sync_all()?;
+    fs::set_permissions(file.path(), metadata.permissions())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // Only root can give the owner back: otherwise keep at least the group.
+        if std::os::unix::fs::fchown(file.as_file(), Some(metadata.uid()), Some(metadata.gid()))
+            .is_err()
+        {
+            let _ = std::os::unix::fs::fchown(file.as_file(), None, Some(metadata.gid()));
+        }
+    }
+
+    if let Some(backup) = backup {
+        fs::copy(path, backup)?;
+    }
+    file.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_line_endings() {
+        assert_eq!(with_line_endings("α\nβ\n", "α\nγ\nβ\n"), "α\nγ\nβ\n");
+        assert_eq!(with_line_endings("α\r\nβ\r\n", "α\r\nβ\r\n"), "α\r\nβ\r\n");
+        assert_eq!(
+            with_line_endings("α\r\nβ\r\n", "α\nγ\r\nβ\r\n"),
+            "α\r\nγ\r\nβ\r\n"
+        );
+        // Mixed line endings are kept.
+        assert_eq!(
+            with_line_endings("α\r\nβ\nγ\n", "α\r\nβ\nδ\nγ\n"),
+            "α\r\nβ\nδ\nγ\n"
+        );
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("κείμενο.txt");
+        fs::write(&path, "Να μην την δώσεις.").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        let backup = backup_path(&path, ".orig");
+        write_atomic(&path, "Να μην τη δώσεις.".as_bytes(), Some(&backup)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Να μην τη δώσεις.");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "Να μην την δώσεις.");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+        // Only the file and its backup, no leftover temporary file.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("κείμενο.txt");
+        let link = dir.path().join("σύνδεσμος.txt");
+        fs::write(&target, "Να μην την δώσεις.").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "Να μην τη δώσεις.".as_bytes(), None).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "Να μην τη δώσεις.");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,254 @@
+//! CSV and TSV files. Ex. glossaries exported from a spreadsheet.
+//!
+//! The first record is the header. Every cell of the selected columns is linted
+//! on its own, and diagnostics are labeled by `row:column`, where rows are
+//! numbered as in a spreadsheet (the header is row 1).
+//!
+//! <https://datatracker.ietf.org/doc/html/rfc4180>
+use std::borrow::Cow;
+
+use super::{Format, Segment, SegmentBuilder, line_col};
+use crate::range::TextRange;
+
+/// CSV format.
+#[derive(Debug, Clone)]
+pub struct Csv {
+    pub delimiter: char,
+    /// Columns to lint, by header name or 1-based number. All if `None`.
+    pub columns: Option<Vec<String>>,
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            columns: None,
+        }
+    }
+}
+
+/// A field of a record, with the range of its raw content.
+struct Field {
+    start: usize,
+    end: usize,
+    quoted: bool,
+}
+
+impl Csv {
+    /// Split `source` into records.
+    fn records(&self, source: &str) -> Result<Vec<Vec<Field>>, String> {
+        let mut records = Vec::new();
+        let mut record = Vec::new();
+        let mut pos = 0;
+
+        while pos < source.len() {
+            let rest = &source[pos..];
+            let field = if rest.starts_with('"') {
+                let mut end = pos + 1;
+                loop {
+                    let Some(idx) = source[end..].find('"') else {
+                        let (line, col) = line_col(source, pos);
+                        return Err(format!("line {line}, column {col}: unterminated field"));
+                    };
+                    end += idx;
+                    if source[end + 1..].starts_with('"') {
+                        end += 2;
+                    } else {
+                        break;
+                    }
+                }
+                let field = Field {
+                    start: pos + 1,
+                    end,
+                    quoted: true,
+                };
+                pos = end + 1;
+                field
+            } else {
+                let len = rest
+                    .find([self.delimiter, '\n', '\r'])
+                    .unwrap_or(rest.len());
+                let field = Field {
+                    start: pos,
+                    end: pos + len,
+                    quoted: false,
+                };
+                pos += len;
+                field
+            };
+            record.push(field);
+
+            let rest = &source[pos..];
+            if rest.starts_with(self.delimiter) {
+                pos += self.delimiter.len_utf8();
+            } else if rest.is_empty() || rest.starts_with(['\n', '\r']) {
+                pos += if rest.starts_with("\r\n") {
+                    2
+                } else {
+                    rest.len().min(1)
+                };
+                records.push(std::mem::take(&mut record));
+            } else {
+                let (line, col) = line_col(source, pos);
+                return Err(format!("line {line}, column {col}: expected a delimiter"));
+            }
+        }
+        if !record.is_empty() {
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+impl Format for Csv {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let records = self.records(source)?;
+        let Some((header, rows)) = records.split_first() else {
+            return Ok(Vec::new());
+        };
+        let names: Vec<_> = header
+            .iter()
+            .map(|field| source[field.start..field.end].replace("\"\"", "\""))
+            .collect();
+
+        let selected: Vec<usize> = match &self.columns {
+            None => (0..names.len()).collect(),
+            Some(columns) => columns
+                .iter()
+                .map(|column| {
+                    if let Some(idx) = names.iter().position(|name| name.trim() == column) {
+                        return Ok(idx);
+                    }
+                    // 1-based column numbers, within the header.
+                    match column.parse::<usize>() {
+                        Ok(number) if (1..=names.len()).contains(&number) => Ok(number - 1),
+                        Ok(number) => Err(format!(
+                            "column {number} out of range, there are {} columns",
+                            names.len()
+                        )),
+                        Err(_) => Err(format!("unknown column {column}")),
+                    }
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        let mut segments = Vec::new();
+        for (idx, record) in rows.iter().enumerate() {
+            for &column in &selected {
+                let Some(field) = record.get(column) else {
+                    continue;
+                };
+                let mut builder = SegmentBuilder::new();
+                let raw = &source[field.start..field.end];
+                if field.quoted {
+                    let mut offset = field.start;
+                    for (i, part) in raw.split("\"\"").enumerate() {
+                        if i > 0 {
+                            builder.synthetic("\"", TextRange::new(offset - 2, offset));
+                        }
+                        builder.verbatim(part, offset);
+                        offset += part.len() + 2;
+                    }
+                } else {
+                    builder.verbatim(raw, field.start);
+                }
+                if builder.is_empty() {
+                    continue;
+                }
+                let name = names.get(column).filter(|name| !name.is_empty());
+                let label = match name {
+                    Some(name) => format!("{}:{name}", idx + 2),
+                    None => format!("{}:{}", idx + 2, column + 1),
+                };
+                segments.push(builder.finish(Some(label)));
+            }
+        }
+        Ok(segments)
+    }
+
+    /// Replacements that would need the cell to be quoted are not applied.
+    fn escape<'a>(&self, replacement: &'a str) -> Option<Cow<'a, str>> {
+        (!replacement.contains([self.delimiter, '"', '\n', '\r']))
+            .then_some(Cow::Borrowed(replacement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "id,title,description\r\n\
+        1,Σπίτι,\"Ένα \"\"μεγάλο\"\", ωραίο\r\nσπίτι\"\r\n\
+        2,Δρόμος,\r\n";
+
+    fn segments(csv: &Csv) -> Vec<(String, String)> {
+        csv.segments(SOURCE)
+            .unwrap()
+            .into_iter()
+            .map(|segment| (segment.label.unwrap(), segment.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_segments() {
+        let csv = Csv {
+            columns: Some(vec!["description".to_string(), "2".to_string()]),
+            ..Csv::default()
+        };
+        let segments = segments(&csv);
+        let segments: Vec<_> = segments
+            .iter()
+            .map(|(label, text)| (label.as_str(), text.as_str()))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                ("2:description", "Ένα \"μεγάλο\", ωραίο\r\nσπίτι"),
+                ("2:title", "Σπίτι"),
+                ("3:title", "Δρόμος"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        let segment = Csv::default().segments(SOURCE).unwrap().remove(2);
+        let start = segment.text.find("ωραίο").unwrap();
+        let range = segment
+            .map
+            .to_source(TextRange::new(start, start + "ωραίο".len()))
+            .unwrap();
+        assert_eq!(&SOURCE[range.start()..range.end()], "ωραίο");
+    }
+
+    #[test]
+    fn test_tsv() {
+        let csv = Csv {
+            delimiter: '\t',
+            columns: None,
+        };
+        let segments = csv.segments("a\tb\nα, β\tγ").unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "α, β");
+    }
+
+    #[test]
+    fn test_malformed() {
+        let csv = Csv::default();
+        assert!(csv.segments("a,b\n\"α,β\n").is_err());
+        assert!(csv.segments("a,b\n\"α\"β,γ\n").is_err());
+        let csv = Csv {
+            columns: Some(vec!["c".to_string()]),
+            ..Csv::default()
+        };
+        assert!(csv.segments("a,b\nα,β\n").is_err());
+        for column in ["0", "3"] {
+            let csv = Csv {
+                columns: Some(vec![column.to_string()]),
+                ..Csv::default()
+            };
+            let err = csv.segments("a,b\nα,β\n").unwrap_err();
+            assert!(err.contains("out of range"), "{err}");
+        }
+    }
+}
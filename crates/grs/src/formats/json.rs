@@ -0,0 +1,282 @@
+//! JSON resource bundles. Ex. `el.json`.
+//!
+//! Only string values are linted, as messages (see [`message`]). Diagnostics
+//! are labeled by the JSON pointer of their value.
+//!
+//! <https://datatracker.ietf.org/doc/html/rfc6901>
+use std::borrow::Cow;
+
+use super::{Format, Segment, SegmentBuilder, glob_match, line_col, message};
+
+/// JSON format.
+#[derive(Debug, Clone, Default)]
+pub struct Json {
+    /// Only lint the values whose JSON pointer matches this pattern.
+    pub keys: Option<String>,
+}
+
+/// Escape sequences of JSON strings.
+///
+/// Surrogates must come in pairs: a lone or invalid surrogate is no escape.
+fn unescape(raw: &str) -> Option<(usize, String)> {
+    let escaped = raw.strip_prefix('\\')?;
+    let text = match escaped.chars().next()? {
+        'n' => "\n",
+        't' => "\t",
+        'r' => "\r",
+        'b' => "\u{8}",
+        'f' => "\u{c}",
+        '"' => "\"",
+        '\\' => "\\",
+        '/' => "/",
+        'u' => {
+            let code = u32::from_str_radix(escaped.get(1..5)?, 16).ok()?;
+            // Surrogate pair. Ex. "😀"
+            if (0xD800..0xDC00).contains(&code)
+                && let Some(low) = escaped.get(5..11)?.strip_prefix("\\u")
+                && let Ok(low) = u32::from_str_radix(low, 16)
+                && (0xDC00..=0xDFFF).contains(&low)
+            {
+                let code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                return Some((12, char::from_u32(code)?.to_string()));
+            }
+            return Some((6, char::from_u32(code)?.to_string()));
+        }
+        _ => return None,
+    };
+    Some((2, text.to_string()))
+}
+
+/// Append a key to a JSON pointer.
+pub(crate) fn push_key(pointer: &str, key: &str) -> String {
+    format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    keys: Option<&'a str>,
+    segments: Vec<Segment>,
+}
+
+impl Parser<'_> {
+    fn error(&self, expected: &str) -> String {
+        let (line, col) = line_col(self.source, self.pos.min(self.source.len()));
+        format!("line {line}, column {col}: expected {expected}")
+    }
+
+    fn whitespace(&mut self) {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.whitespace();
+        let found = self.source[self.pos..].starts_with(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Parse a string, returning the start and the end of its raw content.
+    fn string(&mut self) -> Result<(usize, usize), String> {
+        if !self.eat('"') {
+            return Err(self.error("a string"));
+        }
+        let start = self.pos;
+        let mut pos = start;
+        while let Some(c) = self.source[pos..].chars().next() {
+            match c {
+                '"' => {
+                    self.pos = pos + 1;
+                    return Ok((start, pos));
+                }
+                '\\' => {
+                    let Some((len, _)) = unescape(&self.source[pos..]) else {
+                        self.pos = pos;
+                        return Err(self.error("a valid escape sequence"));
+                    };
+                    pos += len;
+                }
+                _ => pos += c.len_utf8(),
+            }
+        }
+        self.pos = self.source.len();
+        Err(self.error("the end of the string"))
+    }
+
+    fn value(&mut self, pointer: &str) -> Result<(), String> {
+        self.whitespace();
+        let rest = &self.source[self.pos..];
+        if rest.starts_with('{') {
+            self.pos += 1;
+            if self.eat('}') {
+                return Ok(());
+            }
+            loop {
+                let (start, end) = self.string()?;
+                let key = message::decode(&self.source[start..end], &unescape);
+                if !self.eat(':') {
+                    return Err(self.error("':'"));
+                }
+                self.value(&push_key(pointer, &key))?;
+                if self.eat('}') {
+                    return Ok(());
+                }
+                if !self.eat(',') {
+                    return Err(self.error("',' or '}'"));
+                }
+            }
+        } else if rest.starts_with('[') {
+            self.pos += 1;
+            if self.eat(']') {
+                return Ok(());
+            }
+            for idx in 0.. {
+                self.value(&format!("{pointer}/{idx}"))?;
+                if self.eat(']') {
+                    break;
+                }
+                if !self.eat(',') {
+                    return Err(self.error("',' or ']'"));
+                }
+            }
+            Ok(())
+        } else if rest.starts_with('"') {
+            let (start, end) = self.string()?;
+            if self.keys.is_none_or(|pattern| glob_match(pattern, pointer)) {
+                let mut builder = SegmentBuilder::new();
+                message::push(
+                    &mut builder,
+                    &self.source[start..end],
+                    start,
+                    &unescape,
+                    true,
+                );
+                if !builder.is_empty() {
+                    self.segments
+                        .push(builder.finish(Some(pointer.to_string())));
+                }
+            }
+            Ok(())
+        } else {
+            // Numbers, booleans and null.
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c)))
+                .unwrap_or(rest.len());
+            if len == 0 {
+                return Err(self.error("a value"));
+            }
+            self.pos += len;
+            Ok(())
+        }
+    }
+}
+
+impl Format for Json {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let mut parser = Parser {
+            source,
+            pos: 0,
+            keys: self.keys.as_deref(),
+            segments: Vec::new(),
+        };
+        parser.value("")?;
+        parser.whitespace();
+        if parser.pos < source.len() {
+            return Err(parser.error("the end of the file"));
+        }
+        Ok(parser.segments)
+    }
+
+    fn escape<'a>(&self, replacement: &'a str) -> Option<Cow<'a, str>> {
+        if !replacement.contains(|c: char| c == '"' || c == '\\' || c.is_control()) {
+            return Some(Cow::Borrowed(replacement));
+        }
+        let mut escaped = String::with_capacity(replacement.len());
+        for c in replacement.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\t' => escaped.push_str("\\t"),
+                c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                _ => escaped.push(c),
+            }
+        }
+        Some(Cow::Owned(escaped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{
+  "home": {
+    "title": "Καλώς ήρθατε, {name}!",
+    "count": 3,
+    "items": ["Στην \"αρχή\"", "Τέλος \u00e9"]
+  },
+  "a/b": "{n, plural, one {# λέξη} other {# λέξεις}}",
+  "empty": ""
+}"#;
+
+    fn segments(keys: Option<&str>) -> Vec<(String, String)> {
+        let json = Json {
+            keys: keys.map(str::to_string),
+        };
+        json.segments(SOURCE)
+            .unwrap()
+            .into_iter()
+            .map(|segment| (segment.label.unwrap(), segment.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_segments() {
+        let segments = segments(None);
+        let pointers: Vec<_> = segments
+            .iter()
+            .map(|(pointer, _)| pointer.as_str())
+            .collect();
+        assert_eq!(
+            pointers,
+            ["/home/title", "/home/items/0", "/home/items/1", "/a~1b"]
+        );
+        assert_eq!(segments[0].1, "Καλώς ήρθατε, 0!");
+        assert_eq!(segments[1].1, "Στην \"αρχή\"");
+        assert_eq!(segments[2].1, "Τέλος é");
+        assert_eq!(segments[3].1, "0 λέξη\n0 λέξεις");
+    }
+
+    #[test]
+    fn test_keys() {
+        let segments = segments(Some("/home/items/*"));
+        assert_eq!(segments.len(), 2);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let json = Json::default();
+        assert!(json.segments("{\"a\": }").is_err());
+        assert!(json.segments("{\"a\": \"b\"").is_err());
+        assert!(json.segments("[1] 2").is_err());
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("\\u00e9x"), Some((6, "é".to_string())));
+        assert_eq!(unescape("\\ud83d\\ude00"), Some((12, "😀".to_string())));
+        assert_eq!(unescape("\\\"a"), Some((2, "\"".to_string())));
+        assert_eq!(unescape("a"), None);
+        // Unpaired or invalid low surrogates.
+        assert_eq!(unescape("\\ud83d\\u0020"), None);
+        assert_eq!(unescape("\\ud83dx"), None);
+        assert_eq!(unescape("\\ude00"), None);
+        let json = Json::default();
+        let err = json.segments("{\"a\": \"\\ud83d\\u0020\"}").unwrap_err();
+        assert!(err.contains("escape sequence"), "{err}");
+    }
+}
@@ -0,0 +1,481 @@
+//! LaTeX sources.
+//!
+//! Only body text and text arguments are linted. Command names, math, comments,
+//! verbatim and the arguments of referencing commands are skipped, and so is
+//! text set in a foreign language through babel or polyglossia.
+//!
+//! This is not a TeX engine: user macros are seen as unknown commands whose
+//! arguments are treated as text.
+use super::{Format, Segment, SegmentBuilder};
+use crate::range::TextRange;
+
+const MATH_ENVIRONMENTS: [&str; 18] = [
+    "math",
+    "displaymath",
+    "equation",
+    "equation*",
+    "align",
+    "align*",
+    "alignat",
+    "alignat*",
+    "flalign",
+    "flalign*",
+    "gather",
+    "gather*",
+    "multline",
+    "multline*",
+    "eqnarray",
+    "eqnarray*",
+    "split",
+    "dmath",
+];
+
+const VERBATIM_ENVIRONMENTS: [&str; 7] = [
+    "verbatim",
+    "verbatim*",
+    "Verbatim",
+    "lstlisting",
+    "minted",
+    "comment",
+    "filecontents",
+];
+
+/// Environments that take mandatory arguments which are not text.
+#[rustfmt::skip]
+const ENVIRONMENT_ARGUMENTS: [(&str, usize); 8] = [
+    ("tabular", 1), ("tabular*", 2), ("tabularx", 2), ("array", 1),
+    ("minipage", 1), ("thebibliography", 1), ("wrapfigure", 2), ("multicols", 1),
+];
+
+/// Commands whose mandatory arguments render as a reference, a citation etc.
+#[rustfmt::skip]
+const OPAQUE_COMMANDS: [(&str, usize); 18] = [
+    ("ref", 1), ("eqref", 1), ("pageref", 1), ("autoref", 1), ("nameref", 1),
+    ("cref", 1), ("Cref", 1), ("vref", 1),
+    ("cite", 1), ("citep", 1), ("citet", 1), ("parencite", 1), ("textcite", 1),
+    ("autocite", 1), ("footcite", 1),
+    ("url", 1), ("nolinkurl", 1), ("today", 0),
+];
+
+/// Commands whose mandatory arguments are not text and do not render.
+///
+/// Arguments following the skipped ones are treated as text.
+/// Ex. `\href{url}{text}`.
+#[rustfmt::skip]
+const SKIPPED_COMMANDS: [(&str, usize); 39] = [
+    ("label", 1), ("documentclass", 1), ("usepackage", 1), ("RequirePackage", 1),
+    ("input", 1), ("include", 1), ("includeonly", 1), ("includegraphics", 1),
+    ("bibliography", 1), ("bibliographystyle", 1), ("addbibresource", 1),
+    ("graphicspath", 1), ("pagestyle", 1), ("thispagestyle", 1), ("pagenumbering", 1),
+    ("hspace", 1), ("vspace", 1), ("hspace*", 1), ("vspace*", 1),
+    ("setlength", 2), ("addtolength", 2), ("setcounter", 2), ("addtocounter", 2),
+    ("newcommand", 2), ("renewcommand", 2), ("providecommand", 2),
+    ("newenvironment", 3), ("renewenvironment", 3), ("newtheorem", 2),
+    ("color", 1), ("textcolor", 1), ("definecolor", 3), ("fontsize", 2),
+    ("href", 1), ("multicolumn", 2), ("multirow", 2),
+    ("setmainfont", 1), ("setmainlanguage", 1), ("setotherlanguage", 1),
+];
+
+/// Commands that stand for some text.
+const TEXT_COMMANDS: [(&str, &str); 5] = [
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("textellipsis", "…"),
+    ("textendash", "–"),
+    ("textemdash", "—"),
+];
+
+/// Languages that can be set with `\text<language>{...}` under polyglossia.
+const FOREIGN_TEXT_COMMANDS: [&str; 8] = [
+    "textlatin",
+    "textenglish",
+    "textfrench",
+    "textgerman",
+    "textitalian",
+    "textspanish",
+    "textrussian",
+    "textarabic",
+];
+
+/// Return `true` if `language` is a babel or polyglossia name for Greek.
+fn is_greek(language: &str) -> bool {
+    let language = language.trim();
+    language == "el" || language.contains("greek")
+}
+
+fn find_arity(table: &[(&str, usize)], name: &str) -> Option<usize> {
+    table
+        .iter()
+        .find_map(|&(command, arity)| (command == name).then_some(arity))
+}
+
+/// LaTeX format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Latex;
+
+impl Format for Latex {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let mut parser = Parser {
+            source,
+            pos: 0,
+            foreign: false,
+            builder: SegmentBuilder::new(),
+        };
+        parser.text(false);
+        Ok(vec![parser.builder.finish(None)])
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    /// Set by `\selectlanguage` to a non-Greek language.
+    foreign: bool,
+    builder: SegmentBuilder,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn opaque(&mut self, start: usize) {
+        if !self.foreign {
+            self.builder.opaque(TextRange::new(start, self.pos));
+        }
+    }
+
+    fn synthetic(&mut self, text: &str, start: usize) {
+        if !self.foreign {
+            self.builder
+                .synthetic(text, TextRange::new(start, self.pos));
+        }
+    }
+
+    /// Process text until the end of the source, or until the closing brace
+    /// of the current group if `in_group`.
+    fn text(&mut self, in_group: bool) {
+        while let Some(ch) = self.peek() {
+            let start = self.pos;
+            match ch {
+                '%' => self.skip_to("\n", false),
+                '\\' => self.command(),
+                '$' => {
+                    let delimiter = if self.rest().starts_with("$$") {
+                        "$$"
+                    } else {
+                        "$"
+                    };
+                    self.pos += delimiter.len();
+                    self.skip_past(delimiter);
+                    self.opaque(start);
+                }
+                '{' => {
+                    self.pos += 1;
+                    self.text(true);
+                }
+                '}' => {
+                    self.pos += 1;
+                    if in_group {
+                        return;
+                    }
+                }
+                '~' | '&' => {
+                    self.pos += 1;
+                    self.synthetic(" ", start);
+                }
+                _ => {
+                    let rest = self.rest();
+                    let len = rest
+                        .find(['%', '\\', '$', '{', '}', '~', '&'])
+                        .unwrap_or(rest.len());
+                    self.pos += len;
+                    if !self.foreign {
+                        self.builder.verbatim(&rest[..len], start);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move past the next unescaped occurrence of `delimiter`, or to the end.
+    fn skip_past(&mut self, delimiter: &str) {
+        while let Some(ch) = self.peek() {
+            if self.rest().starts_with(delimiter) {
+                self.pos += delimiter.len();
+                return;
+            }
+            self.pos += ch.len_utf8();
+            if ch == '\\' {
+                self.pos += self.peek().map_or(0, char::len_utf8);
+            }
+        }
+    }
+
+    /// Move to the next occurrence of `needle` (past it if `past`), or to the end.
+    fn skip_to(&mut self, needle: &str, past: bool) {
+        let rest = self.rest();
+        self.pos +=
+            rest.find(needle).map_or(
+                rest.len(),
+                |idx| {
+                    if past { idx + needle.len() } else { idx }
+                },
+            );
+    }
+
+    /// Skip a balanced `open`..`close` group if it starts at the current position
+    /// (ignoring whitespace when `skip_ws`) and return its content.
+    fn skip_group(&mut self, open: char, close: char, skip_ws: bool) -> Option<&'a str> {
+        let save = self.pos;
+        if skip_ws {
+            self.skip_whitespace();
+        }
+        if self.peek() != Some(open) {
+            self.pos = save;
+            return None;
+        }
+        self.pos += open.len_utf8();
+
+        let rest = self.rest();
+        let mut depth = 0;
+        let mut chars = rest.char_indices();
+        while let Some((idx, ch)) = chars.next() {
+            if ch == '\\' {
+                chars.next();
+            } else if ch == open {
+                depth += 1;
+            } else if ch == close {
+                if depth == 0 {
+                    self.pos += idx + close.len_utf8();
+                    return Some(&rest[..idx]);
+                }
+                depth -= 1;
+            }
+        }
+        self.pos = self.source.len();
+        Some(rest)
+    }
+
+    /// Skip a mandatory argument, which can also be a single command.
+    /// Ex. `\newcommand\foo{...}`
+    fn skip_argument(&mut self) -> Option<&'a str> {
+        if let Some(content) = self.skip_group('{', '}', true) {
+            return Some(content);
+        }
+        if self.peek() == Some('\\') {
+            self.pos += 1;
+            self.command_name();
+        }
+        None
+    }
+
+    fn skip_optional_arguments(&mut self) {
+        while self.skip_group('[', ']', false).is_some() {}
+    }
+
+    fn command_name(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphabetic() && c != '@')
+            .unwrap_or(rest.len());
+        let len = if len == 0 {
+            // Control symbol: \%, \\, \, etc.
+            rest.chars().next().map_or(0, char::len_utf8)
+        } else if rest[len..].starts_with('*') {
+            len + 1
+        } else {
+            len
+        };
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn command(&mut self) {
+        let start = self.pos;
+        self.pos += 1;
+        let name = self.command_name();
+
+        match name {
+            "" => {}
+            "%" | "&" | "$" | "#" | "_" | "{" | "}" => self.synthetic(name, start),
+            "\\" => {
+                self.skip_optional_arguments();
+                self.synthetic("\n", start);
+            }
+            " " | "," | ";" | ":" | "!" | "quad" | "qquad" => self.synthetic(" ", start),
+            // Hyphenation hints and italic corrections.
+            "-" | "/" => self.synthetic("", start),
+            "(" => {
+                self.skip_past("\\)");
+                self.opaque(start);
+            }
+            "[" => {
+                self.skip_past("\\]");
+                self.opaque(start);
+            }
+            "verb" | "verb*" => {
+                if let Some(delimiter) = self.peek() {
+                    self.pos += delimiter.len_utf8();
+                    self.skip_to(delimiter.encode_utf8(&mut [0; 4]), true);
+                }
+                self.opaque(start);
+            }
+            "begin" => self.environment(start),
+            "end" => {
+                self.skip_argument();
+            }
+            "foreignlanguage" => {
+                self.skip_optional_arguments();
+                let language = self.skip_argument().unwrap_or_default();
+                if !is_greek(language) {
+                    let arg_start = self.pos;
+                    self.skip_argument();
+                    self.opaque(arg_start);
+                }
+            }
+            "selectlanguage" => {
+                let language = self.skip_argument().unwrap_or_default();
+                self.foreign = !is_greek(language);
+            }
+            _ if FOREIGN_TEXT_COMMANDS.contains(&name) => {
+                self.skip_optional_arguments();
+                self.skip_argument();
+                self.opaque(start);
+            }
+            _ => {
+                if let Some(arity) = find_arity(&OPAQUE_COMMANDS, name) {
+                    self.skip_optional_arguments();
+                    for _ in 0..arity {
+                        self.skip_argument();
+                    }
+                    self.opaque(start);
+                } else if let Some(arity) = find_arity(&SKIPPED_COMMANDS, name) {
+                    for _ in 0..arity {
+                        self.skip_optional_arguments();
+                        self.skip_argument();
+                    }
+                    self.skip_optional_arguments();
+                } else if let Some(&(_, text)) =
+                    TEXT_COMMANDS.iter().find(|(command, _)| *command == name)
+                {
+                    self.synthetic(text, start);
+                } else {
+                    // Unknown command: its arguments, if any, are text.
+                    self.skip_optional_arguments();
+                }
+            }
+        }
+    }
+
+    fn environment(&mut self, start: usize) {
+        let name = self.skip_argument().unwrap_or_default();
+        let end = format!("\\end{{{name}}}");
+
+        if MATH_ENVIRONMENTS.contains(&name) {
+            self.skip_to(&end, true);
+            self.opaque(start);
+        } else if VERBATIM_ENVIRONMENTS.contains(&name) {
+            self.skip_to(&end, true);
+        } else if name == "otherlanguage" || name == "otherlanguage*" {
+            self.skip_optional_arguments();
+            let language = self.skip_argument().unwrap_or_default();
+            if !is_greek(language) {
+                self.skip_to(&end, true);
+            }
+        } else {
+            self.skip_optional_arguments();
+            for _ in 0..find_arity(&ENVIRONMENT_ARGUMENTS, name).unwrap_or(0) {
+                self.skip_argument();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats;
+    use crate::registry::Rule;
+
+    fn prose(source: &str) -> String {
+        Latex.segments(source).unwrap().remove(0).text
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(prose(r"Το \textbf{σπίτι} μου."), "Το σπίτι μου.");
+        assert_eq!(prose(r"\section{Εισαγωγή}"), "Εισαγωγή");
+        assert_eq!(
+            prose(r"Βλ. \ref{fig:1} και \cite[σ. 5]{key}."),
+            "Βλ. 0 και 0."
+        );
+        assert_eq!(prose("\\label{sec:a}Κείμενο"), "Κείμενο");
+        assert_eq!(prose(r"\href{https://el.wikipedia.org}{σελίδα}"), "σελίδα");
+    }
+
+    #[test]
+    fn test_math() {
+        assert_eq!(prose(r"αν $x = y$ τότε"), "αν 0 τότε");
+        assert_eq!(prose(r"αν \(x\) και \[y\]"), "αν 0 και 0");
+        assert_eq!(
+            prose("α\n\\begin{equation}\nx = 1\n\\end{equation}\nβ"),
+            "α\n0\nβ"
+        );
+    }
+
+    #[test]
+    fn test_comments_and_verbatim() {
+        assert_eq!(prose("α % σχόλιο\nβ"), "α \nβ");
+        assert_eq!(prose(r"100\% σωστό"), "100% σωστό");
+        assert_eq!(
+            prose(r"η εντολή \verb|ls -a| εμφανίζει"),
+            "η εντολή 0 εμφανίζει"
+        );
+        assert_eq!(prose("α\\begin{verbatim}\n%x\n\\end{verbatim}β"), "αβ");
+    }
+
+    #[test]
+    fn test_languages() {
+        assert_eq!(
+            prose(r"Η λέξη \foreignlanguage{english}{monad} σημαίνει"),
+            "Η λέξη 0 σημαίνει"
+        );
+        assert_eq!(prose(r"\foreignlanguage{polutonikogreek}{λόγος}"), "λόγος");
+        assert_eq!(
+            prose(r"α \selectlanguage{english}the text \selectlanguage{greek}β"),
+            "α β"
+        );
+        assert_eq!(
+            prose("α\\begin{otherlanguage}{english}text\\end{otherlanguage}β"),
+            "αβ"
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let source = r"\textbf{Aλλά} $x$ στην διάθεσή σας";
+        let diagnostics = formats::check(&Latex, source, &[Rule::RemoveFinalN]).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        let range = diagnostics[0].range;
+        assert_eq!(&source[range.start()..range.end()], "στην");
+    }
+
+    #[test]
+    fn test_fix() {
+        let source = r"\emph{Aλλά} $x = y$ \label{a}";
+        let fixed = formats::fix(&Latex, source, &[Rule::MixedScripts])
+            .unwrap()
+            .text;
+        assert_eq!(fixed, r"\emph{Αλλά} $x = y$ \label{a}");
+    }
+}
@@ -0,0 +1,172 @@
+//! Markdown (CommonMark, with tables, footnotes, math and YAML front matter).
+//!
+//! Code, math, HTML and front matter are skipped. Blocks are separated by an
+//! empty line in the prose, so that sentences do not run across headings,
+//! list items or table cells.
+use std::borrow::Cow;
+
+use pulldown_cmark::{Event, Options, Parser, TagEnd};
+
+use super::{Format, Segment, SegmentBuilder};
+use crate::range::TextRange;
+
+/// Markdown format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Markdown;
+
+const OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_FOOTNOTES)
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_TASKLISTS)
+    .union(Options::ENABLE_MATH)
+    .union(Options::ENABLE_HEADING_ATTRIBUTES)
+    .union(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+
+const fn is_inline(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Superscript
+            | TagEnd::Subscript
+            | TagEnd::Link
+            | TagEnd::Image
+    )
+}
+
+/// Blocks whose content is not prose.
+const fn is_skipped(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::CodeBlock | TagEnd::HtmlBlock | TagEnd::MetadataBlock(_)
+    )
+}
+
+struct Builder {
+    builder: SegmentBuilder,
+    /// Whether the prose ends with a block separator.
+    separated: bool,
+}
+
+impl Builder {
+    fn separate(&mut self, offset: usize) {
+        if !self.separated && !self.builder.is_empty() {
+            self.builder
+                .synthetic("\n\n", TextRange::new(offset, offset));
+            self.separated = true;
+        }
+    }
+
+    fn text(&mut self, source: &str, text: &str, range: TextRange) {
+        if &source[range.start()..range.end()] == text {
+            self.builder.verbatim(text, range.start());
+        } else {
+            // Escapes and entities.
+            self.builder.synthetic(text, range);
+        }
+        self.separated = false;
+    }
+
+    fn opaque(&mut self, range: TextRange) {
+        self.builder.opaque(range);
+        self.separated = false;
+    }
+}
+
+impl Format for Markdown {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let mut builder = Builder {
+            builder: SegmentBuilder::new(),
+            separated: false,
+        };
+        let mut skipped = 0;
+
+        for (event, range) in Parser::new_ext(source, OPTIONS).into_offset_iter() {
+            let range = TextRange::new(range.start, range.end);
+            match event {
+                Event::Start(tag) => {
+                    let end = tag.to_end();
+                    if is_skipped(&end) {
+                        skipped += 1;
+                    }
+                    if !is_inline(&end) {
+                        builder.separate(range.start());
+                    }
+                }
+                Event::End(end) => {
+                    if is_skipped(&end) {
+                        skipped -= 1;
+                    }
+                    if !is_inline(&end) {
+                        builder.separate(range.end());
+                    }
+                }
+                _ if skipped > 0 => {}
+                Event::Text(text) => builder.text(source, &text, range),
+                Event::SoftBreak => builder.text(source, "\n", range),
+                Event::HardBreak => {
+                    builder.builder.synthetic("\n", range);
+                    builder.separated = false;
+                }
+                Event::Code(_)
+                | Event::InlineMath(_)
+                | Event::DisplayMath(_)
+                | Event::FootnoteReference(_) => builder.opaque(range),
+                Event::Html(_) | Event::InlineHtml(_) | Event::Rule | Event::TaskListMarker(_) => {}
+            }
+        }
+        Ok(vec![builder.builder.finish(None)])
+    }
+
+    /// Replacements with Markdown syntax are not applied.
+    fn escape<'a>(&self, replacement: &'a str) -> Option<Cow<'a, str>> {
+        (!replacement.contains(['\\', '`', '*', '_', '[', ']', '<', '>', '&', '|', '$']))
+            .then_some(Cow::Borrowed(replacement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(source: &str) -> String {
+        Markdown.segments(source).unwrap().remove(0).text
+    }
+
+    #[test]
+    fn test_prose() {
+        let source = "---\ntitle: Τίτλος\n---\n\n# Κεφάλαιο\n\nΈνα *ωραίο* [σπίτι](σπίτι.md) με `κώδικα`\nκαι $x$.\n\n```\nκώδικας\n```\n\n- α\n- β\n";
+        assert_eq!(
+            prose(source),
+            "Κεφάλαιο\n\nΈνα ωραίο σπίτι με 0\nκαι 0.\n\nα\n\nβ\n\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let source = "| α | β |\n|---|---|\n| γ | δ |\n";
+        assert_eq!(prose(source), "α\n\nβ\n\nγ\n\nδ\n\n");
+    }
+
+    #[test]
+    fn test_source_map() {
+        let source = "> Να μην **την** δώσεις.\n";
+        let segment = Markdown.segments(source).unwrap().remove(0);
+        let start = segment.text.find("την").unwrap();
+        let range = segment
+            .map
+            .to_source(TextRange::new(start, start + "την".len()))
+            .unwrap();
+        assert_eq!(&source[range.start()..range.end()], "την");
+    }
+
+    #[test]
+    fn test_escapes() {
+        let segment = Markdown.segments("α \\* β &amp; γ").unwrap().remove(0);
+        assert_eq!(segment.text, "α * β & γ\n\n");
+        let start = segment.text.find('γ').unwrap();
+        let range = TextRange::new(start, start + 'γ'.len_utf8());
+        assert!(segment.map.to_source(range).is_some());
+    }
+}
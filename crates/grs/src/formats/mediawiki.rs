@@ -0,0 +1,186 @@
+//! Streaming reader for MediaWiki XML dumps.
+//!
+//! Pages are read one at a time, so that dumps of any size can be processed
+//! without loading them into memory. Bzip2 compressed dumps (`.xml.bz2`) are
+//! decompressed on the fly.
+//!
+//! <https://www.mediawiki.org/wiki/Help:Export#Export_format>
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use bzip2::read::MultiBzDecoder;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+
+/// A page of a dump, with the text of its (last) revision.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Page {
+    pub title: String,
+    /// Namespace. Articles are in the namespace 0.
+    pub ns: i64,
+    pub revision: u64,
+    pub redirect: bool,
+    pub text: String,
+}
+
+/// Open a dump, decompressing it if its extension is `.bz2`.
+///
+/// # Errors
+///
+/// Returns an error message if the file can not be opened.
+pub fn open(path: &Path) -> Result<Pages<Box<dyn BufRead>>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let reader: Box<dyn BufRead> = if path.extension().is_some_and(|ext| ext == "bz2") {
+        Box::new(BufReader::new(MultiBzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    Ok(Pages::new(reader))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    None,
+    Title,
+    Ns,
+    PageId,
+    RevisionId,
+    Text,
+}
+
+/// Iterator over the pages of a dump.
+pub struct Pages<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl<R: BufRead> Pages<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Reader::from_reader(reader),
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+
+    fn next_page(&mut self) -> Result<Option<Page>, String> {
+        let mut page = None::<Page>;
+        let mut field = Field::None;
+        let mut in_revision = false;
+
+        loop {
+            self.buf.clear();
+            let event = self
+                .reader
+                .read_event_into(&mut self.buf)
+                .map_err(|err| format!("at position {}: {err}", self.reader.error_position()))?;
+
+            match event {
+                Event::Start(tag) => match tag.name().as_ref() {
+                    b"page" => page = Some(Page::default()),
+                    b"revision" => in_revision = true,
+                    b"title" => field = Field::Title,
+                    b"ns" => field = Field::Ns,
+                    b"id" if in_revision => field = Field::RevisionId,
+                    b"id" => field = Field::PageId,
+                    b"text" => field = Field::Text,
+                    _ => field = Field::None,
+                },
+                Event::Empty(tag) => {
+                    if tag.name().as_ref() == b"redirect"
+                        && let Some(page) = page.as_mut()
+                    {
+                        page.redirect = true;
+                    }
+                }
+                Event::Text(text) => {
+                    let Some(page) = page.as_mut() else {
+                        continue;
+                    };
+                    let text = text.unescape().map_err(|err| err.to_string())?;
+                    match field {
+                        Field::Title => page.title.push_str(&text),
+                        Field::Text => page.text.push_str(&text),
+                        Field::Ns => page.ns = text.trim().parse().unwrap_or_default(),
+                        // Only the first revision id, not the contributor's.
+                        Field::RevisionId if page.revision == 0 => {
+                            page.revision = text.trim().parse().unwrap_or_default();
+                        }
+                        _ => {}
+                    }
+                }
+                Event::End(tag) => match tag.name().as_ref() {
+                    b"page" => return Ok(page),
+                    b"revision" => in_revision = false,
+                    _ => field = Field::None,
+                },
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Pages<R> {
+    type Item = Result<Page, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let page = self.next_page().transpose();
+        if !matches!(page, Some(Ok(_))) {
+            self.done = true;
+        }
+        page
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">
+  <siteinfo><sitename>Βικιπαίδεια</sitename></siteinfo>
+  <page>
+    <title>Αθήνα</title>
+    <ns>0</ns>
+    <id>1</id>
+    <revision>
+      <id>42</id>
+      <contributor><username>X</username><id>7</id></contributor>
+      <text bytes="10" xml:space="preserve">Η &lt;b&gt;πόλη&lt;/b&gt; [[Ελλάδα|της Ελλάδας]].</text>
+    </revision>
+  </page>
+  <page>
+    <title>Αθηνα</title>
+    <ns>0</ns>
+    <id>2</id>
+    <redirect title="Αθήνα" />
+    <revision>
+      <id>43</id>
+      <text>#ΑΝΑΚΑΤΕΥΘΥΝΣΗ [[Αθήνα]]</text>
+    </revision>
+  </page>
+</mediawiki>"#;
+
+    #[test]
+    fn test_pages() {
+        let pages: Vec<_> = Pages::new(DUMP.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(pages.len(), 2);
+
+        let page = &pages[0];
+        assert_eq!(page.title, "Αθήνα");
+        assert_eq!(page.ns, 0);
+        assert_eq!(page.revision, 42);
+        assert!(!page.redirect);
+        assert_eq!(page.text, "Η <b>πόλη</b> [[Ελλάδα|της Ελλάδας]].");
+
+        assert!(pages[1].redirect);
+        assert_eq!(pages[1].revision, 43);
+    }
+}
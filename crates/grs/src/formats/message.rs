@@ -0,0 +1,278 @@
+//! Translatable messages.
+//!
+//! Interpolation placeholders (`%s`, `{name}`, `{{name}}`, `%{name}`) are
+//! opaque. ICU `MessageFormat` arguments are opaque too, except for the
+//! sub-messages of `plural`, `select` and `selectordinal`, which are prose.
+//! ICU apostrophe quoting (`''`, `'{...}'`) is only handled in message
+//! bundles, not in gettext catalogs, where an apostrophe is just an apostrophe.
+//!
+//! <https://unicode-org.github.io/icu/userguide/format_parse/messages/>
+use super::{OPAQUE, SegmentBuilder, placeholder_len};
+use crate::range::TextRange;
+
+/// Escape sequence at the start of the raw text: its length and its value.
+pub type Unescape<'u> = &'u dyn Fn(&str) -> Option<(usize, String)>;
+
+/// Push the message `raw`, which starts at `offset` in the source, with ICU
+/// apostrophe quoting if `icu_quotes`.
+pub fn push(
+    builder: &mut SegmentBuilder,
+    raw: &str,
+    offset: usize,
+    unescape: Unescape,
+    icu_quotes: bool,
+) {
+    let mut parser = Parser {
+        raw,
+        offset,
+        unescape,
+        icu_quotes,
+        builder,
+        verbatim_start: 0,
+    };
+    parser.message(0, raw.len(), false);
+    parser.flush(raw.len());
+}
+
+/// Decode the escape sequences of `raw`, without handling placeholders.
+pub fn decode(raw: &str, unescape: Unescape) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut pos = 0;
+    while let Some(c) = raw[pos..].chars().next() {
+        if let Some((len, unescaped)) = unescape(&raw[pos..]) {
+            text.push_str(&unescaped);
+            pos += len;
+        } else {
+            text.push(c);
+            pos += c.len_utf8();
+        }
+    }
+    text
+}
+
+struct Parser<'a, 'b> {
+    raw: &'a str,
+    offset: usize,
+    unescape: Unescape<'a>,
+    icu_quotes: bool,
+    builder: &'b mut SegmentBuilder,
+    /// Start of the raw text not yet pushed.
+    verbatim_start: usize,
+}
+
+/// Find the `}` matching the `{` at the start of `text`.
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_name(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+impl Parser<'_, '_> {
+    /// Push the raw text up to `pos` as is.
+    fn flush(&mut self, pos: usize) {
+        if self.verbatim_start < pos {
+            let text = &self.raw[self.verbatim_start..pos];
+            self.builder
+                .verbatim(text, self.offset + self.verbatim_start);
+        }
+        self.verbatim_start = pos;
+    }
+
+    /// Replace the raw text from `pos` to `end` by `text`.
+    fn synthetic(&mut self, text: &str, pos: usize, end: usize) {
+        self.flush(pos);
+        let range = TextRange::new(self.offset + pos, self.offset + end);
+        self.builder.synthetic(text, range);
+        self.verbatim_start = end;
+    }
+
+    /// Drop the raw text from `pos` to `end`.
+    fn skip(&mut self, pos: usize, end: usize) {
+        self.flush(pos);
+        self.verbatim_start = end;
+    }
+
+    fn message(&mut self, mut pos: usize, end: usize, in_plural: bool) {
+        while pos < end {
+            let rest = &self.raw[pos..end];
+            if let Some((len, text)) = (self.unescape)(rest) {
+                self.synthetic(&text, pos, pos + len);
+                pos += len;
+            } else if rest.starts_with("%%") {
+                pos += 2;
+            } else if rest.starts_with("{{") || rest.starts_with("%{") {
+                // Mustache and ruby interpolations.
+                let close = if rest.starts_with("{{") { "}}" } else { "}" };
+                match rest.find(close) {
+                    Some(idx) => {
+                        let len = idx + close.len();
+                        self.synthetic(OPAQUE, pos, pos + len);
+                        pos += len;
+                    }
+                    None => pos += 1,
+                }
+            } else if rest.starts_with('{') {
+                pos = self.argument(pos, end);
+            } else if in_plural && rest.starts_with('#') {
+                self.synthetic(OPAQUE, pos, pos + 1);
+                pos += 1;
+            } else if self.icu_quotes && rest.starts_with("''") {
+                self.synthetic("'", pos, pos + 2);
+                pos += 2;
+            } else if self.icu_quotes && (rest.starts_with("'{") || rest.starts_with("'}")) {
+                // Quoted literal text, up to the end if the quote is not closed.
+                self.skip(pos, pos + 1);
+                match rest[1..].find('\'') {
+                    Some(idx) => {
+                        self.flush(pos + idx + 1);
+                        self.skip(pos + idx + 1, pos + idx + 2);
+                        pos += idx + 2;
+                    }
+                    None => {
+                        self.flush(end);
+                        pos = end;
+                    }
+                }
+            } else if let Some(len) = placeholder_len(rest) {
+                self.synthetic(OPAQUE, pos, pos + len);
+                pos += len;
+            } else {
+                pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+
+    /// Handle the argument starting at `pos`, returning the position after it.
+    fn argument(&mut self, pos: usize, end: usize) -> usize {
+        let Some(close) = matching_brace(&self.raw[pos..end]) else {
+            return pos + 1;
+        };
+        let close = pos + close;
+        let inner = &self.raw[pos + 1..close];
+        let mut parts = inner.splitn(3, ',');
+        let name = parts.next().unwrap_or_default().trim();
+        if !is_name(name) {
+            // Not an argument. Ex. "{ δύο λέξεις }"
+            return pos + 1;
+        }
+
+        let kind = parts.next().map(str::trim);
+        let Some(style) = parts.next() else {
+            self.synthetic(OPAQUE, pos, close + 1);
+            return close + 1;
+        };
+        if !matches!(kind, Some("plural" | "select" | "selectordinal")) {
+            self.synthetic(OPAQUE, pos, close + 1);
+            return close + 1;
+        }
+
+        // Sub-messages: `selector {message}` pairs, each message on its own.
+        let in_plural = kind != Some("select");
+        let mut sub = close - style.len();
+        let mut first = true;
+        while let Some(open) = self.raw[sub..close].find('{') {
+            let open = sub + open;
+            let Some(len) = matching_brace(&self.raw[open..close + 1]) else {
+                break;
+            };
+            if first {
+                self.skip(pos, open + 1);
+            } else {
+                self.synthetic("\n", sub, open + 1);
+            }
+            self.message(open + 1, open + len, in_plural);
+            self.flush(open + len);
+            sub = open + len;
+            first = false;
+        }
+        self.skip(sub, close + 1);
+        close + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(raw: &str) -> String {
+        let mut builder = SegmentBuilder::new();
+        push(&mut builder, raw, 0, &|_| None, true);
+        builder.finish(None).text
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(prose("Γεια σου {name}!"), "Γεια σου 0!");
+        assert_eq!(prose("Γεια σου {{ name }}!"), "Γεια σου 0!");
+        assert_eq!(prose("Γεια σου %{name}, %s."), "Γεια σου 0, 0.");
+        assert_eq!(prose("100%% { δύο λέξεις }"), "100%% { δύο λέξεις }");
+    }
+
+    #[test]
+    fn test_icu() {
+        assert_eq!(
+            prose("Έχετε {count, plural, =0 {κανένα μήνυμα} one {# μήνυμα} other {# μηνύματα}}."),
+            "Έχετε κανένα μήνυμα\n0 μήνυμα\n0 μηνύματα."
+        );
+        assert_eq!(
+            prose("{gender, select, female {Η {name}} other {Ο {name}}} ήρθε"),
+            "Η 0\nΟ 0 ήρθε"
+        );
+        assert_eq!(prose("Στις {date, date, short}"), "Στις 0");
+        assert_eq!(
+            prose("Το '{'όνομα'}' και το ''α''"),
+            "Το {όνομα} και το 'α'"
+        );
+        assert_eq!(prose("'{αβγ"), "{αβγ");
+
+        let mut builder = SegmentBuilder::new();
+        push(&mut builder, "Το ''α'' '{'", 0, &|_| None, false);
+        assert_eq!(builder.finish(None).text, "Το ''α'' '{'");
+    }
+
+    #[test]
+    fn test_unclosed_quote() {
+        let raw = "'{αβγ";
+        let mut builder = SegmentBuilder::new();
+        push(&mut builder, raw, 0, &|_| None, true);
+        let segment = builder.finish(None);
+        let start = segment.text.find("αβγ").unwrap();
+        let range = segment
+            .map
+            .to_source(TextRange::new(start, start + "αβγ".len()))
+            .unwrap();
+        assert_eq!(&raw[range.start()..range.end()], "αβγ");
+    }
+
+    #[test]
+    fn test_source_map() {
+        let raw = "{n, plural, one {# λέξη} other {# λέξεις}}";
+        let mut builder = SegmentBuilder::new();
+        push(&mut builder, raw, 10, &|_| None, true);
+        let segment = builder.finish(None);
+        let start = segment.text.find("λέξεις").unwrap();
+        let range = segment
+            .map
+            .to_source(TextRange::new(start, start + "λέξεις".len()))
+            .unwrap();
+        assert_eq!(&raw[range.start() - 10..range.end() - 10], "λέξεις");
+    }
+}
@@ -0,0 +1,534 @@
+//! Structured input formats.
+//!
+//! A format extracts the prose of a source file into [`Segment`]s, which are
+//! linted as plain text. Diagnostics are then mapped back to the source through
+//! the [`SourceMap`] of their segment.
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::diagnostic::{Diagnostic, Edit, Fix, ReportOptions, Suggestion};
+use crate::linter::{Config, Counter, FixReport, RuleSet, get_rich_context_message};
+use crate::range::TextRange;
+
+pub mod csv;
+pub mod json;
+pub mod latex;
+pub mod markdown;
+pub mod mediawiki;
+pub mod message;
+pub mod po;
+pub mod wikitext;
+pub mod yaml;
+
+/// Text that stands in the prose for an opaque construct (math, references...).
+///
+/// Numbers are treated as punctuation by the tokenizer, and rules are careful
+/// not to report anything because of them: they make a neutral placeholder.
+pub const OPAQUE: &str = "0";
+
+/// Length of the format placeholder at the start of `text`, if any.
+///
+/// Both printf (`%s`, `%1$d`, `%(name)s`) and brace (`{name}`, `{0}`) styles
+/// are recognized.
+pub fn placeholder_len(text: &str) -> Option<usize> {
+    if let Some(rest) = text.strip_prefix('{') {
+        let close = rest.find('}')?;
+        let (name, _spec) = rest[..close]
+            .split_once(':')
+            .unwrap_or((&rest[..close], ""));
+        let is_name = name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+        return is_name.then_some(close + 2);
+    }
+
+    let rest = text.strip_prefix('%')?;
+    let mut len = 1;
+    let mut chars = rest.chars().peekable();
+    if let Some(name) = rest.strip_prefix('(') {
+        let close = name.find(')')?;
+        len += close + 2;
+        chars = rest[close + 2..].chars().peekable();
+    }
+    // Argument position, flags, width, precision and length modifiers.
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || "$-+ #.*'hlLqjzt".contains(*c)) {
+        len += c.len_utf8();
+    }
+    chars
+        .next()
+        .filter(|c| "diouxXeEfFgGaAcspn@".contains(*c))
+        .map(|_| len + 1)
+}
+
+/// Options of the structured formats.
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Also lint the fuzzy entries of PO files.
+    pub include_fuzzy: bool,
+    /// Only lint the values of JSON and YAML files whose JSON pointer matches
+    /// this pattern, see [`glob_match`].
+    pub keys: Option<String>,
+    /// Read all files as CSV.
+    pub csv: bool,
+    /// Only lint these columns of CSV files, by header name or 1-based number.
+    pub columns: Option<Vec<String>>,
+}
+
+/// Match a JSON pointer against a pattern, where `*` matches any part of a key
+/// and `**` any number of keys. Ex. `/errors/**`, `/*/title`.
+pub fn glob_match(pattern: &str, pointer: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**") {
+        return (0..=pointer.len())
+            .filter(|&idx| pointer.is_char_boundary(idx))
+            .any(|idx| glob_match(rest, &pointer[idx..]));
+    }
+    if let Some(rest) = pattern.strip_prefix('*') {
+        let key_len = pointer.find('/').unwrap_or(pointer.len());
+        return (0..=key_len)
+            .filter(|&idx| pointer.is_char_boundary(idx))
+            .any(|idx| glob_match(rest, &pointer[idx..]));
+    }
+    match (pattern.chars().next(), pointer.chars().next()) {
+        (Some(p), Some(c)) if p == c => {
+            glob_match(&pattern[p.len_utf8()..], &pointer[c.len_utf8()..])
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    /// Start of the chunk in the segment text.
+    start: usize,
+    /// Length of the chunk in the segment text.
+    len: usize,
+    /// Start of the chunk in the source.
+    source: usize,
+    /// Length of the chunk in the source.
+    source_len: usize,
+    /// Whether the chunk was copied as is from the source.
+    verbatim: bool,
+}
+
+/// Mapping from the offsets of a segment text to the offsets of its source.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    chunks: Vec<Chunk>,
+}
+
+impl SourceMap {
+    /// The chunk containing `pos`, preferring the one that starts at `pos`.
+    fn chunk_at(&self, pos: usize) -> Option<&Chunk> {
+        let idx = self.chunks.partition_point(|chunk| chunk.start <= pos);
+        self.chunks[..idx]
+            .iter()
+            .rev()
+            .find(|chunk| pos <= chunk.start + chunk.len)
+    }
+
+    /// Map a range of the segment text to the source.
+    ///
+    /// Returns `None` if the range is not a verbatim copy of the source, in which
+    /// case a replacement over it can not be safely applied.
+    pub fn to_source(&self, range: TextRange) -> Option<TextRange> {
+        let chunk = self.chunk_at(range.start())?;
+        if !chunk.verbatim || range.end() > chunk.start + chunk.len {
+            return None;
+        }
+        let start = chunk.source + range.start() - chunk.start;
+        Some(TextRange::new(start, start + range.end() - range.start()))
+    }
+
+    /// Map a range of the segment text to the smallest source range covering it.
+    pub fn to_source_lossy(&self, range: TextRange) -> TextRange {
+        let start = self.chunk_at(range.start()).map_or(0, |chunk| {
+            if chunk.verbatim {
+                chunk.source + range.start() - chunk.start
+            } else {
+                chunk.source
+            }
+        });
+        let end = self
+            .chunks
+            .iter()
+            .rev()
+            .find(|chunk| chunk.start < range.end())
+            .map_or(start, |chunk| {
+                if chunk.verbatim {
+                    chunk.source + (range.end() - chunk.start).min(chunk.len)
+                } else {
+                    chunk.source + chunk.source_len
+                }
+            });
+        TextRange::new(start, end.max(start))
+    }
+}
+
+/// A piece of prose extracted from a source.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// Where the segment comes from, when the source position is not enough.
+    /// Ex. the key of a translation.
+    pub label: Option<String>,
+    pub text: String,
+    pub map: SourceMap,
+}
+
+/// Incrementally build a [`Segment`] out of pieces of a source.
+#[derive(Debug, Default)]
+pub struct SegmentBuilder {
+    text: String,
+    map: SourceMap,
+}
+
+impl SegmentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Copy `text`, which starts at `offset` in the source.
+    pub fn verbatim(&mut self, text: &str, offset: usize) {
+        if text.is_empty() {
+            return;
+        }
+        let start = self.text.len();
+        self.text.push_str(text);
+
+        // Merge with the previous chunk if it is contiguous in both texts.
+        if let Some(last) = self.map.chunks.last_mut()
+            && last.verbatim
+            && last.start + last.len == start
+            && last.source + last.source_len == offset
+        {
+            last.len += text.len();
+            last.source_len += text.len();
+            return;
+        }
+
+        self.map.chunks.push(Chunk {
+            start,
+            len: text.len(),
+            source: offset,
+            source_len: text.len(),
+            verbatim: true,
+        });
+    }
+
+    /// Push `text`, which stands for the `source` range without being a copy of it.
+    /// Ex. an unescaped character.
+    pub fn synthetic(&mut self, text: &str, source: TextRange) {
+        self.map.chunks.push(Chunk {
+            start: self.text.len(),
+            len: text.len(),
+            source: source.start(),
+            source_len: source.end() - source.start(),
+            verbatim: false,
+        });
+        self.text.push_str(text);
+    }
+
+    /// Push a placeholder for an opaque construct of the source.
+    pub fn opaque(&mut self, source: TextRange) {
+        self.synthetic(OPAQUE, source);
+    }
+
+    pub fn finish(self, label: Option<String>) -> Segment {
+        Segment {
+            label,
+            text: self.text,
+            map: self.map,
+        }
+    }
+}
+
+/// A structured input format.
+pub trait Format {
+    /// Extract the prose of `source`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if `source` is malformed.
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String>;
+
+    /// Encode a replacement so that it can be written in the source.
+    ///
+    /// Returns `None` if the replacement can not be represented.
+    fn escape<'a>(&self, replacement: &'a str) -> Option<Cow<'a, str>> {
+        Some(Cow::Borrowed(replacement))
+    }
+}
+
+/// Return the format to use for `path`, if it is not plain text.
+pub fn from_path(path: &Path, options: &FormatOptions) -> Option<Box<dyn Format>> {
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    if options.csv || matches!(extension, Some("csv" | "tsv")) {
+        return Some(Box::new(csv::Csv {
+            delimiter: if extension == Some("tsv") { '\t' } else { ',' },
+            columns: options.columns.clone(),
+        }));
+    }
+    match extension {
+        Some("tex") => Some(Box::new(latex::Latex)),
+        Some("md" | "markdown") => Some(Box::new(markdown::Markdown)),
+        Some("po" | "pot") => Some(Box::new(po::Po {
+            include_fuzzy: options.include_fuzzy,
+        })),
+        Some("json") => Some(Box::new(json::Json {
+            keys: options.keys.clone(),
+        })),
+        Some("yaml" | "yml") => Some(Box::new(yaml::Yaml {
+            keys: options.keys.clone(),
+        })),
+        Some("wiki" | "mediawiki") => Some(Box::new(wikitext::Wikitext)),
+        _ => None,
+    }
+}
+
+/// Return `true` if `path` can be linted, either as plain text or as a known format.
+pub fn is_supported(path: &Path, options: &FormatOptions) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("txt")
+        || from_path(path, options).is_some()
+}
+
+/// 1-based line and column (in characters) of a byte `offset` of `source`.
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+    let col = before[line_start..].chars().count() + 1;
+    (line, col)
+}
+
+/// Map a diagnostic of a segment back to the source.
+///
+/// Suggestions are dropped if one of their edits does not fall on a verbatim
+/// part of the source.
+fn to_source(format: &dyn Format, segment: &Segment, diagnostic: Diagnostic) -> Diagnostic {
+    let suggestions = diagnostic
+        .suggestions
+        .into_iter()
+        .filter_map(|suggestion| {
+            let edits = suggestion
+                .fix
+                .edits()
+                .iter()
+                .map(|edit| {
+                    let range = segment.map.to_source(edit.range)?;
+                    let content = format.escape(&edit.content)?.into_owned();
+                    Some(Edit::replacement(content, range))
+                })
+                .collect::<Option<_>>()?;
+            Some(Suggestion {
+                fix: Fix::new(edits),
+                ..suggestion
+            })
+        })
+        .collect();
+    Diagnostic {
+        kind: diagnostic.kind,
+        range: segment.map.to_source_lossy(diagnostic.range),
+        suggestions,
+        severity: diagnostic.severity,
+        message: diagnostic.message,
+    }
+}
+
+/// Check the prose of `source`, returning diagnostics in source coordinates.
+///
+/// # Errors
+///
+/// Returns an error message if `source` is malformed.
+pub fn check(format: &dyn Format, source: &str, config: Config) -> Result<Vec<Diagnostic>, String> {
+    check_with(format, source, &RuleSet::new(config))
+}
+
+/// Like [`check`], with the rules of a config already dispatched.
+///
+/// # Errors
+///
+/// Returns an error message if `source` is malformed.
+pub fn check_with(
+    format: &dyn Format,
+    source: &str,
+    rules: &RuleSet,
+) -> Result<Vec<Diagnostic>, String> {
+    let mut diagnostics = Vec::new();
+    for segment in format.segments(source)? {
+        diagnostics.extend(
+            rules
+                .check(&segment.text)
+                .into_iter()
+                .map(|diagnostic| to_source(format, &segment, diagnostic)),
+        );
+    }
+    Ok(diagnostics)
+}
+
+/// Lint the prose of `source`.
+///
+/// Messages are prefixed by the line and column of the diagnostic in the source,
+/// followed by the segment label if any.
+///
+/// # Errors
+///
+/// Returns an error message if `source` is malformed.
+pub fn lint_only(
+    format: &dyn Format,
+    source: &str,
+    config: Config,
+    report: &ReportOptions,
+) -> Result<(Vec<String>, Counter), String> {
+    lint_only_filtered(format, source, config, report, |_| true)
+}
+
+/// Like [`lint_only`], but only report the diagnostics whose source range is kept.
+///
+/// # Errors
+///
+/// Returns an error message if `source` is malformed.
+pub fn lint_only_filtered<F>(
+    format: &dyn Format,
+    source: &str,
+    config: Config,
+    report: &ReportOptions,
+    keep: F,
+) -> Result<(Vec<String>, Counter), String>
+where
+    F: Fn(TextRange) -> bool,
+{
+    let rules = RuleSet::new(config);
+    let mut messages = Vec::new();
+    let mut statistics = Counter::new();
+    for segment in format.segments(source)? {
+        for diagnostic in rules.check(&segment.text) {
+            let range = segment.map.to_source_lossy(diagnostic.range);
+            if !keep(range) {
+                continue;
+            }
+            *statistics.entry(diagnostic.kind).or_insert(0) += 1;
+            let (line, col) = line_col(source, range.start());
+            let location = match &segment.label {
+                Some(label) => format!("{line}:{col} ({label})"),
+                None => format!("{line}:{col}"),
+            };
+            let message = get_rich_context_message(&segment.text, &diagnostic, report);
+            messages.push(format!("{location} {message}"));
+        }
+    }
+    Ok((messages, statistics))
+}
+
+/// Repeatedly fix the prose of `source` until stable.
+///
+/// # Errors
+///
+/// Returns an error message if `source` is malformed.
+pub fn fix(format: &dyn Format, source: &str, config: Config) -> Result<FixReport, String> {
+    // Fail early on malformed sources.
+    format.segments(source)?;
+    Ok(crate::linter::fix_with(source, config, |text, rules| {
+        check_with(format, text, rules).unwrap_or_default()
+    }))
+}
+
+/// Like [`fix`], but only apply the fixes of the diagnostics whose source range
+/// is kept.
+///
+/// `keep` is called with the source of the current pass.
+///
+/// # Errors
+///
+/// Returns an error message if `source` is malformed.
+pub fn fix_filtered<F>(
+    format: &dyn Format,
+    source: &str,
+    config: Config,
+    keep: F,
+) -> Result<FixReport, String>
+where
+    F: Fn(&str, TextRange) -> bool,
+{
+    format.segments(source)?;
+    Ok(crate::linter::fix_with(source, config, |text, rules| {
+        let mut diagnostics = check_with(format, text, rules).unwrap_or_default();
+        diagnostics.retain(|diagnostic| keep(text, diagnostic.range));
+        diagnostics
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_map() {
+        // Source: "ab\"cd", prose: "ab"cd" (the escape is unescaped).
+        let mut builder = SegmentBuilder::new();
+        builder.verbatim("ab", 0);
+        builder.synthetic("\"", TextRange::new(2, 4));
+        builder.verbatim("cd", 4);
+        let segment = builder.finish(None);
+
+        assert_eq!(segment.text, "ab\"cd");
+        let map = &segment.map;
+        assert_eq!(
+            map.to_source(TextRange::new(0, 2)),
+            Some(TextRange::new(0, 2))
+        );
+        assert_eq!(
+            map.to_source(TextRange::new(3, 5)),
+            Some(TextRange::new(4, 6))
+        );
+        assert_eq!(map.to_source(TextRange::new(1, 4)), None);
+        assert_eq!(
+            map.to_source_lossy(TextRange::new(1, 4)),
+            TextRange::new(1, 5)
+        );
+    }
+
+    #[test]
+    fn test_merge_contiguous() {
+        let mut builder = SegmentBuilder::new();
+        builder.verbatim("ab", 3);
+        builder.verbatim("cd", 5);
+        let segment = builder.finish(None);
+        let range = segment.map.to_source(TextRange::new(1, 3));
+        assert_eq!(range, Some(TextRange::new(4, 6)));
+    }
+
+    #[test]
+    fn test_placeholder_len() {
+        assert_eq!(placeholder_len("%s και"), Some(2));
+        assert_eq!(placeholder_len("%1$d"), Some(4));
+        assert_eq!(placeholder_len("%(name)s."), Some(8));
+        assert_eq!(placeholder_len("%-5.2f"), Some(6));
+        assert_eq!(placeholder_len("{name}"), Some(6));
+        assert_eq!(placeholder_len("{0:>3}"), Some(6));
+        assert_eq!(placeholder_len("{όνομα}"), Some("{όνομα}".len()));
+        assert_eq!(placeholder_len("% των"), None);
+        assert_eq!(placeholder_len("{ a b }"), None);
+        assert_eq!(placeholder_len("λέξη"), None);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("/errors/**", "/errors/login/title"));
+        assert!(glob_match("/*/title", "/home/title"));
+        assert!(!glob_match("/*/title", "/home/menu/title"));
+        assert!(glob_match("**/title", "/home/menu/title"));
+        assert!(glob_match("/menu_*", "/menu_main"));
+        assert!(!glob_match("/menu", "/menu/open"));
+    }
+
+    #[test]
+    fn test_line_col() {
+        let source = "αβ\nγδ";
+        assert_eq!(line_col(source, 0), (1, 1));
+        assert_eq!(line_col(source, "αβ\nγ".len()), (2, 2));
+    }
+}
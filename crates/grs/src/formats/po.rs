@@ -0,0 +1,278 @@
+//! Gettext PO files.
+//!
+//! Only translations (`msgstr` and `msgstr[n]`) are linted. The header, obsolete
+//! entries and, unless asked for, fuzzy entries are skipped. Placeholders are
+//! opaque, see [`message`].
+//!
+//! <https://www.gnu.org/software/gettext/manual/html_node/PO-Files.html>
+use std::borrow::Cow;
+
+use super::{Format, Segment, SegmentBuilder, message};
+
+/// PO format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Po {
+    /// Also lint entries flagged as fuzzy.
+    pub include_fuzzy: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Ctxt,
+    Id,
+    IdPlural,
+    Str,
+}
+
+#[derive(Default)]
+struct Entry {
+    fuzzy: bool,
+    /// The context and the source text, as written (escaped) in the file.
+    ctxt: Option<String>,
+    id: String,
+    strs: Vec<(Option<usize>, SegmentBuilder)>,
+}
+
+impl Entry {
+    fn label(&self, plural: Option<usize>) -> String {
+        let mut label = String::new();
+        if let Some(ctxt) = &self.ctxt {
+            label.push_str(&format!("msgctxt \"{ctxt}\" "));
+        }
+        label.push_str(&format!("msgid \"{}\"", self.id));
+        if let Some(idx) = plural {
+            label.push_str(&format!(" msgstr[{idx}]"));
+        }
+        label
+    }
+}
+
+/// Escape sequences of PO strings.
+fn unescape(raw: &str) -> Option<(usize, String)> {
+    let escaped = raw.strip_prefix('\\')?.chars().next()?;
+    let text = match escaped {
+        'n' => "\n",
+        't' => "\t",
+        'r' => "\r",
+        '"' => "\"",
+        '\\' => "\\",
+        _ => " ",
+    };
+    Some((1 + escaped.len_utf8(), text.to_string()))
+}
+
+/// Split a line into its keyword and the content of its string literal, with the
+/// offset of the content in the line.
+fn split_line(line: &str) -> Option<(&str, &str, usize)> {
+    let open = line.find('"')?;
+    let close = line.rfind('"').filter(|&close| close > open)?;
+    Some((line[..open].trim(), &line[open + 1..close], open + 1))
+}
+
+impl Format for Po {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let mut segments = Vec::new();
+        let mut entry = Entry::default();
+        let mut field = None;
+
+        let mut flush = |entry: &mut Entry| {
+            let entry = std::mem::take(entry);
+            // The header is the translation of the empty string.
+            if entry.id.is_empty() || (entry.fuzzy && !self.include_fuzzy) {
+                return;
+            }
+            let labels: Vec<_> = entry
+                .strs
+                .iter()
+                .map(|(plural, _)| entry.label(*plural))
+                .collect();
+            for ((_, builder), label) in entry.strs.into_iter().zip(labels) {
+                if !builder.is_empty() {
+                    segments.push(builder.finish(Some(label)));
+                }
+            }
+        };
+
+        let mut offset = 0;
+        for (idx, line) in source.split_inclusive('\n').enumerate() {
+            let line_offset = offset;
+            offset += line.len();
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+            let trimmed = trimmed.trim_end();
+
+            if trimmed.is_empty() {
+                continue;
+            }
+            if let Some(comment) = trimmed.strip_prefix('#') {
+                if !entry.strs.is_empty() {
+                    flush(&mut entry);
+                }
+                field = None;
+                if let Some(flags) = comment.strip_prefix(',')
+                    && flags.split(',').any(|flag| flag.trim() == "fuzzy")
+                {
+                    entry.fuzzy = true;
+                }
+                continue;
+            }
+
+            let Some((keyword, content, content_offset)) = split_line(trimmed) else {
+                return Err(format!("line {}: expected a string", idx + 1));
+            };
+            let content_offset = line_offset + indent + content_offset;
+
+            if !keyword.is_empty() {
+                let plural = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|rest| rest.strip_suffix(']'));
+                let next = match keyword {
+                    "msgctxt" => Field::Ctxt,
+                    "msgid" => Field::Id,
+                    "msgid_plural" => Field::IdPlural,
+                    "msgstr" => Field::Str,
+                    _ if plural.is_some() => Field::Str,
+                    _ => return Err(format!("line {}: unknown keyword {keyword}", idx + 1)),
+                };
+                if matches!(next, Field::Ctxt | Field::Id) && !entry.strs.is_empty() {
+                    flush(&mut entry);
+                }
+                match next {
+                    Field::Ctxt => entry.ctxt = Some(String::new()),
+                    Field::Id => entry.id.clear(),
+                    Field::IdPlural => {}
+                    Field::Str => {
+                        let plural = plural.map(|n| n.trim().parse().unwrap_or_default());
+                        entry.strs.push((plural, SegmentBuilder::new()));
+                    }
+                }
+                field = Some(next);
+            }
+
+            match field {
+                Some(Field::Ctxt) => entry.ctxt.get_or_insert_default().push_str(content),
+                Some(Field::Id) => entry.id.push_str(content),
+                Some(Field::IdPlural) => {}
+                Some(Field::Str) => {
+                    if let Some((_, builder)) = entry.strs.last_mut() {
+                        message::push(builder, content, content_offset, &unescape, false);
+                    }
+                }
+                None => return Err(format!("line {}: unexpected string", idx + 1)),
+            }
+        }
+        flush(&mut entry);
+
+        Ok(segments)
+    }
+
+    fn escape<'a>(&self, replacement: &'a str) -> Option<Cow<'a, str>> {
+        if !replacement.contains(['"', '\\', '\n', '\t']) {
+            return Some(Cow::Borrowed(replacement));
+        }
+        let mut escaped = String::with_capacity(replacement.len());
+        for c in replacement.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\t' => escaped.push_str("\\t"),
+                _ => escaped.push(c),
+            }
+        }
+        Some(Cow::Owned(escaped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::TextRange;
+
+    const SOURCE: &str = r#"# Μετάφραση.
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#: src/main.c:10
+msgctxt "menu"
+msgid "Open %s"
+msgstr "Άνοιγμα %s"
+
+#, fuzzy
+msgid "Close"
+msgstr "Κλείσιμο"
+
+msgid "One file"
+msgid_plural "{n} files"
+msgstr[0] "Ένα αρχείο"
+msgstr[1] "{n} αρχεία "
+"\"μαζί\""
+
+#~ msgid "Old"
+#~ msgstr "Παλιό"
+"#;
+
+    #[test]
+    fn test_segments() {
+        let segments = Po::default().segments(SOURCE).unwrap();
+        let texts: Vec<_> = segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+        assert_eq!(texts, ["Άνοιγμα 0", "Ένα αρχείο", "0 αρχεία \"μαζί\""]);
+
+        let labels: Vec<_> = segments
+            .iter()
+            .map(|segment| segment.label.as_deref())
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                Some("msgctxt \"menu\" msgid \"Open %s\""),
+                Some("msgid \"One file\" msgstr[0]"),
+                Some("msgid \"One file\" msgstr[1]"),
+            ]
+        );
+
+        // The continuation line maps back to the source.
+        let segment = &segments[2];
+        let start = segment.text.find("μαζί").unwrap();
+        let range = segment
+            .map
+            .to_source(TextRange::new(start, start + "μαζί".len()));
+        let range = range.unwrap();
+        assert_eq!(&SOURCE[range.start()..range.end()], "μαζί");
+    }
+
+    #[test]
+    fn test_fuzzy() {
+        let po = Po {
+            include_fuzzy: true,
+        };
+        let segments = po.segments(SOURCE).unwrap();
+        assert!(segments.iter().any(|segment| segment.text == "Κλείσιμο"));
+    }
+
+    #[test]
+    fn test_apostrophes() {
+        let source = "msgid \"a\"\nmsgstr \"Το ''α'' '{'\"\n";
+        let segments = Po::default().segments(source).unwrap();
+        assert_eq!(segments[0].text, "Το ''α'' '{'");
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert!(Po::default().segments("msgid \"a\"\nmsgstr\n").is_err());
+        assert!(Po::default().segments("\"a\"\n").is_err());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(Po::default().escape("«α»"), Some(Cow::Borrowed("«α»")));
+        assert_eq!(
+            Po::default().escape("\"α\"\n").as_deref(),
+            Some("\\\"α\\\"\\n")
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! MediaWiki markup.
+//!
+//! Templates, tables, references, comments and non-prose tags are stripped, and
+//! links are reduced to their displayed text, so that only prose is linted.
+use super::{Format, Segment, SegmentBuilder};
+use crate::range::TextRange;
+
+/// Link namespaces that do not render as text (files, categories, interwikis).
+#[rustfmt::skip]
+const HIDDEN_LINK_PREFIXES: [&str; 14] = [
+    "Αρχείο:", "Εικόνα:", "Κατηγορία:", "File:", "Image:", "Category:",
+    "αρχείο:", "εικόνα:", "κατηγορία:", "file:", "image:", "category:",
+    "Media:", "Μέσο:",
+];
+
+/// Tags whose content is not prose.
+#[rustfmt::skip]
+const SKIPPED_TAGS: [&str; 11] = [
+    "ref", "math", "chem", "code", "pre", "syntaxhighlight", "source",
+    "gallery", "timeline", "score", "references",
+];
+
+const ENTITIES: [(&str, &str); 9] = [
+    ("&nbsp;", " "),
+    ("&amp;", "&"),
+    ("&lt;", "<"),
+    ("&gt;", ">"),
+    ("&quot;", "\""),
+    ("&ndash;", "–"),
+    ("&mdash;", "—"),
+    ("&hellip;", "…"),
+    ("&laquo;", "«"),
+];
+
+/// Return `true` if `target` is an interwiki link. Ex. `en:Word`.
+fn is_interwiki(target: &str) -> bool {
+    target.split_once(':').is_some_and(|(prefix, _)| {
+        (2..=3).contains(&prefix.len()) && prefix.chars().all(|c| c.is_ascii_lowercase())
+    })
+}
+
+/// MediaWiki markup format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wikitext;
+
+impl Format for Wikitext {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let mut parser = Parser {
+            source,
+            pos: 0,
+            builder: SegmentBuilder::new(),
+        };
+        parser.inline(source.len());
+        Ok(vec![parser.builder.finish(None)])
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    builder: SegmentBuilder,
+}
+
+impl Parser<'_> {
+    fn at_line_start(&self) -> bool {
+        self.pos == 0 || self.source[..self.pos].ends_with('\n')
+    }
+
+    /// Move past the next occurrence of `needle`, or to `end`.
+    fn skip_past(&mut self, needle: &str, end: usize) {
+        self.pos = self.source[self.pos..end]
+            .find(needle)
+            .map_or(end, |idx| self.pos + idx + needle.len());
+    }
+
+    /// Move past the balanced `open`..`close` construct starting at the current position.
+    fn skip_balanced(&mut self, open: &str, close: &str, end: usize) {
+        let mut depth = 0;
+        while self.pos < end {
+            let rest = &self.source[self.pos..end];
+            if rest.starts_with(open) {
+                depth += 1;
+                self.pos += open.len();
+            } else if rest.starts_with(close) {
+                depth -= 1;
+                self.pos += close.len();
+                if depth == 0 {
+                    return;
+                }
+            } else {
+                self.pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+
+    /// Process markup from the current position up to `end`.
+    fn inline(&mut self, end: usize) {
+        while self.pos < end {
+            let start = self.pos;
+            let rest = &self.source[self.pos..end];
+
+            if self.at_line_start() && self.line_start(end) {
+                continue;
+            }
+
+            if rest.starts_with("<!--") {
+                self.skip_past("-->", end);
+            } else if rest.starts_with("{{") {
+                self.skip_balanced("{{", "}}", end);
+                self.builder.opaque(TextRange::new(start, self.pos));
+            } else if rest.starts_with("[[") {
+                self.link(end);
+            } else if rest.starts_with("[http") || rest.starts_with("[//") {
+                self.external_link(end);
+            } else if rest.starts_with("''") {
+                self.pos += rest.len() - rest.trim_start_matches('\'').len();
+            } else if rest.starts_with('<') {
+                self.tag(end);
+            } else if rest.starts_with('&')
+                && let Some(&(entity, text)) =
+                    ENTITIES.iter().find(|(entity, _)| rest.starts_with(entity))
+            {
+                self.pos += entity.len();
+                self.builder
+                    .synthetic(text, TextRange::new(start, self.pos));
+            } else if rest.starts_with("__")
+                && let Some(len) = rest[2..].find("__")
+                && rest[2..2 + len].chars().all(|c| c.is_ascii_uppercase())
+                && len > 0
+            {
+                // Magic words. Ex. __TOC__
+                self.pos += len + 4;
+            } else {
+                // Plain text up to the next markup candidate, or the end of the line.
+                let first = rest.chars().next().map_or(1, char::len_utf8);
+                let len = if rest.starts_with('\n') {
+                    1
+                } else {
+                    rest[first..]
+                        .find(['<', '{', '[', '\'', '&', '_', '\n'])
+                        .map_or(rest.len(), |idx| idx + first)
+                };
+                self.pos += len;
+                self.builder.verbatim(&rest[..len], start);
+            }
+        }
+    }
+
+    /// Handle line-level markup: tables, headings and list markers.
+    ///
+    /// Returns `true` if the current position moved.
+    fn line_start(&mut self, end: usize) -> bool {
+        let start = self.pos;
+        let rest = &self.source[self.pos..end];
+        let line_len = rest.find('\n').unwrap_or(rest.len());
+        let line = &rest[..line_len];
+
+        if line.starts_with("{|") {
+            self.skip_balanced("{|", "|}", end);
+        } else if line.starts_with('=') && line.trim_end().ends_with('=') {
+            let inner = line.trim_end().trim_matches('=');
+            let inner_start = start + line.len() - line.trim_start_matches('=').len();
+            self.pos = inner_start;
+            self.inline(inner_start + inner.len());
+            self.pos = start + line_len;
+        } else if line.starts_with(['*', '#', ':', ';']) {
+            self.pos += line.len() - line.trim_start_matches(['*', '#', ':', ';']).len();
+        }
+
+        self.pos != start
+    }
+
+    fn link(&mut self, end: usize) {
+        let start = self.pos;
+        self.skip_balanced("[[", "]]", end);
+        let inner_end = if self.source[..self.pos].ends_with("]]") {
+            self.pos - 2
+        } else {
+            self.pos
+        };
+        let inner = &self.source[start + 2..inner_end.max(start + 2)];
+
+        let target = inner.split('|').next().unwrap_or_default().trim();
+        if HIDDEN_LINK_PREFIXES
+            .iter()
+            .any(|prefix| target.starts_with(prefix))
+            || is_interwiki(target)
+        {
+            return;
+        }
+
+        // The displayed text is the last part of the link.
+        let label_start = inner
+            .rfind('|')
+            .map_or(start + 2, |idx| start + 2 + idx + 1);
+        let after = self.pos;
+        self.pos = label_start;
+        self.inline(inner_end.max(label_start));
+        self.pos = after;
+    }
+
+    fn external_link(&mut self, end: usize) {
+        let start = self.pos;
+        let rest = &self.source[self.pos..end];
+        let Some(close) = rest.find(']') else {
+            self.pos = end;
+            return;
+        };
+        let inner = &rest[1..close];
+        self.pos = start + close + 1;
+        match inner.find(' ') {
+            Some(idx) => {
+                let after = self.pos;
+                self.pos = start + 1 + idx + 1;
+                self.inline(start + close);
+                self.pos = after;
+            }
+            None => self.builder.opaque(TextRange::new(start, self.pos)),
+        }
+    }
+
+    fn tag(&mut self, end: usize) {
+        let start = self.pos;
+        let rest = &self.source[self.pos..end];
+        let Some(close) = rest.find('>') else {
+            self.builder.verbatim(&rest[..1], start);
+            self.pos += 1;
+            return;
+        };
+        let tag = &rest[1..close];
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase();
+        if name.is_empty() {
+            // Not a tag. Ex. "x < y"
+            self.builder.verbatim(&rest[..1], start);
+            self.pos += 1;
+            return;
+        }
+
+        self.pos += close + 1;
+        let self_closing = tag.ends_with('/');
+        if SKIPPED_TAGS.contains(&name.as_str()) && !tag.starts_with('/') && !self_closing {
+            self.skip_past(&format!("</{name}>"), end);
+            if name != "ref" && name != "references" {
+                self.builder.opaque(TextRange::new(start, self.pos));
+            }
+        } else if name == "br" {
+            self.builder
+                .synthetic("\n", TextRange::new(start, self.pos));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prose(source: &str) -> String {
+        Wikitext.segments(source).unwrap().remove(0).text
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(prose("το [[σπίτι]] μου"), "το σπίτι μου");
+        assert_eq!(prose("τα [[πόδι]]α μου"), "τα πόδια μου");
+        assert_eq!(prose("η [[Αθήνα|πρωτεύουσα]]."), "η πρωτεύουσα.");
+        assert_eq!(prose("α[[Αρχείο:Α.jpg|thumb|λεζάντα [[β]]]]γ"), "αγ");
+        assert_eq!(prose("α [[en:Word]]γ"), "α γ");
+        assert_eq!(prose("δες [https://el.wikipedia.org εδώ]"), "δες εδώ");
+    }
+
+    #[test]
+    fn test_templates_and_refs() {
+        assert_eq!(prose("το {{lang|en|word}} λέει"), "το 0 λέει");
+        assert_eq!(prose("{{a|{{b}}}}κείμενο"), "0κείμενο");
+        assert_eq!(prose("λέξη<ref name=\"a\">Πηγή</ref>."), "λέξη.");
+        assert_eq!(prose("λέξη<ref name=\"a\"/>."), "λέξη.");
+        assert_eq!(prose("α<!-- σχόλιο -->β"), "αβ");
+    }
+
+    #[test]
+    fn test_line_markup() {
+        assert_eq!(prose("== Ιστορία ==\nκείμενο"), " Ιστορία \nκείμενο");
+        assert_eq!(prose("* στοιχείο\n# άλλο"), " στοιχείο\n άλλο");
+        assert_eq!(prose("α\n{| class=\"x\"\n| κελί\n|}\nβ"), "α\n\nβ");
+        assert_eq!(prose("'''Η''' ''λέξη''&nbsp;__TOC__"), "Η λέξη ");
+    }
+}
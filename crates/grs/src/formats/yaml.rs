@@ -0,0 +1,537 @@
+//! YAML resource bundles. Ex. `el.yaml`.
+//!
+//! Only string values are linted, as messages (see [`message`]). Diagnostics
+//! are labeled by the JSON pointer of their value.
+//!
+//! This is the block subset of YAML that resource bundles are written in: flow
+//! collections (`{a: b}`, `[a, b]`) and complex keys are not supported.
+use std::borrow::Cow;
+
+use super::json::push_key;
+use super::{Format, Segment, SegmentBuilder, glob_match, message};
+use crate::range::TextRange;
+
+/// YAML format.
+#[derive(Debug, Clone, Default)]
+pub struct Yaml {
+    /// Only lint the values whose JSON pointer matches this pattern.
+    pub keys: Option<String>,
+}
+
+/// Replace a line break and the indentation that follows, as in flow scalars.
+fn fold(raw: &str) -> Option<(usize, String)> {
+    if !raw.starts_with(['\n', '\r']) {
+        return None;
+    }
+    let len = raw.len() - raw.trim_start().len();
+    let breaks = raw[..len].matches('\n').count();
+    let text = if breaks > 1 {
+        "\n".repeat(breaks - 1)
+    } else {
+        " ".to_string()
+    };
+    Some((len, text))
+}
+
+/// Escape sequences of double-quoted scalars.
+fn unescape_double(raw: &str) -> Option<(usize, String)> {
+    let Some(escaped) = raw.strip_prefix('\\') else {
+        return fold(raw);
+    };
+    let c = escaped.chars().next()?;
+    let hex = |len: usize| {
+        let code = u32::from_str_radix(escaped.get(1..=len)?, 16).ok()?;
+        Some((len + 2, char::from_u32(code)?.to_string()))
+    };
+    let text = match c {
+        'x' => return hex(2),
+        'u' => return hex(4),
+        'U' => return hex(8),
+        // Escaped line break.
+        '\n' | '\r' => {
+            let len = 1 + escaped.len() - escaped.trim_start().len();
+            return Some((len, String::new()));
+        }
+        'n' => "\n",
+        't' | '\t' => "\t",
+        'r' => "\r",
+        '0' => "\0",
+        'a' => "\u{7}",
+        'b' => "\u{8}",
+        'e' => "\u{1b}",
+        'f' => "\u{c}",
+        'v' => "\u{b}",
+        ' ' => " ",
+        '"' => "\"",
+        '/' => "/",
+        '\\' => "\\",
+        'N' => "\u{85}",
+        '_' => "\u{a0}",
+        'L' => "\u{2028}",
+        'P' => "\u{2029}",
+        _ => return None,
+    };
+    Some((1 + c.len_utf8(), text.to_string()))
+}
+
+/// Escape sequences of single-quoted scalars.
+fn unescape_single(raw: &str) -> Option<(usize, String)> {
+    if raw.starts_with("''") {
+        Some((2, "'".to_string()))
+    } else {
+        fold(raw)
+    }
+}
+
+fn no_unescape(_: &str) -> Option<(usize, String)> {
+    None
+}
+
+/// Return `true` if a plain scalar is not a string.
+fn is_plain_non_string(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "~" | "null" | "true" | "false" | "yes" | "no" | "on" | "off"
+    ) || value.parse::<f64>().is_ok()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Line<'a> {
+    offset: usize,
+    /// The line, without its line break.
+    text: &'a str,
+    /// Start of the content. Items of sequences move it past the `- `.
+    col: usize,
+}
+
+impl<'a> Line<'a> {
+    fn content(&self) -> &'a str {
+        &self.text[self.col..]
+    }
+
+    fn is_blank(&self) -> bool {
+        let content = self.text.trim_start();
+        content.is_empty()
+            || content.starts_with('#')
+            || (self.col == 0 && (content.starts_with("---") || content.starts_with("...")))
+            || (self.col == 0 && content.starts_with('%'))
+    }
+
+    fn is_item(&self) -> bool {
+        let content = self.content();
+        content == "-" || content.starts_with("- ")
+    }
+}
+
+/// Split a mapping line into its key and the column of its value.
+fn split_key(content: &str) -> Option<(String, usize)> {
+    let (key, after) =
+        if let Some(quote) = content.chars().next().filter(|&c| c == '"' || c == '\'') {
+            let close = content[1..].find(quote)? + 1;
+            let key = &content[1..close];
+            let key = if quote == '"' {
+                message::decode(key, &unescape_double)
+            } else {
+                message::decode(key, &unescape_single)
+            };
+            (key, close + 1)
+        } else {
+            let idx = content
+                .find(": ")
+                .or_else(|| content.ends_with(':').then(|| content.len() - 1))?;
+            (content[..idx].trim_end().to_string(), idx)
+        };
+    let rest = &content[after..];
+    let colon = rest.len() - rest.trim_start().len();
+    rest[colon..]
+        .starts_with(':')
+        .then_some((key, after + colon + 1))
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    lines: Vec<Line<'a>>,
+    idx: usize,
+    keys: Option<&'a str>,
+    segments: Vec<Segment>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {message}", self.idx + 1)
+    }
+
+    fn skip_blank(&mut self) {
+        while self.idx < self.lines.len() && self.lines[self.idx].is_blank() {
+            self.idx += 1;
+        }
+    }
+
+    fn current(&self) -> Option<Line<'a>> {
+        self.lines.get(self.idx).copied()
+    }
+
+    /// Parse the node starting at the current line, if it is indented by at
+    /// least `min_indent`.
+    fn node(&mut self, min_indent: usize, pointer: &str) -> Result<(), String> {
+        self.skip_blank();
+        let Some(line) = self.current() else {
+            return Ok(());
+        };
+        if line.col < min_indent {
+            return Ok(());
+        }
+        if line.is_item() {
+            self.sequence(line.col, pointer)
+        } else if split_key(line.content()).is_some() {
+            self.mapping(line.col, pointer)
+        } else {
+            self.value(line.col, min_indent.saturating_sub(1), pointer)
+        }
+    }
+
+    fn mapping(&mut self, col: usize, pointer: &str) -> Result<(), String> {
+        loop {
+            self.skip_blank();
+            let Some(line) = self.current() else {
+                return Ok(());
+            };
+            if line.col < col || (line.col == col && line.is_item()) {
+                return Ok(());
+            }
+            if line.col > col {
+                return Err(self.error("bad indentation"));
+            }
+            let Some((key, value_col)) = split_key(line.content()) else {
+                return Err(self.error("expected a mapping key"));
+            };
+            self.value(col + value_col, col, &push_key(pointer, &key))?;
+        }
+    }
+
+    fn sequence(&mut self, col: usize, pointer: &str) -> Result<(), String> {
+        for idx in 0.. {
+            self.skip_blank();
+            let Some(line) = self.current() else {
+                return Ok(());
+            };
+            if line.col != col || !line.is_item() {
+                if line.col > col {
+                    return Err(self.error("bad indentation"));
+                }
+                return Ok(());
+            }
+            let item = &line.content()[1..];
+            let item_col = col + 1 + item.len() - item.trim_start().len();
+            let pointer = format!("{pointer}/{idx}");
+            if item.trim().is_empty() {
+                self.idx += 1;
+                self.node(col + 1, &pointer)?;
+            } else {
+                self.lines[self.idx].col = item_col;
+                self.node(item_col, &pointer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the value starting at `col` of the current line, for a parent node
+    /// indented by `parent`.
+    fn value(&mut self, col: usize, parent: usize, pointer: &str) -> Result<(), String> {
+        let line = self.lines[self.idx];
+        let mut col = col;
+        loop {
+            let rest = &line.text[col..];
+            col += rest.len() - rest.trim_start().len();
+            // Tags and anchors.
+            let rest = &line.text[col..];
+            if !rest.starts_with(['!', '&']) {
+                break;
+            }
+            col += rest.find(' ').unwrap_or(rest.len());
+        }
+        let rest = &line.text[col..];
+        let lint = self.keys.is_none_or(|pattern| glob_match(pattern, pointer));
+
+        if rest.is_empty() || rest.starts_with('#') {
+            self.idx += 1;
+            self.skip_blank();
+            match self.current() {
+                Some(next) if next.col > parent => self.node(parent + 1, pointer),
+                Some(next) if next.col == parent && next.is_item() => {
+                    self.sequence(parent, pointer)
+                }
+                _ => Ok(()),
+            }
+        } else if rest.starts_with('*') {
+            // Alias.
+            self.idx += 1;
+            Ok(())
+        } else if rest.starts_with(['{', '[']) {
+            Err(self.error("flow collections are not supported"))
+        } else if rest.starts_with(['|', '>']) {
+            self.block_scalar(rest, parent, lint, pointer);
+            Ok(())
+        } else if let Some(quote) = rest.chars().next().filter(|&c| c == '"' || c == '\'') {
+            self.quoted(line.offset + col, quote, lint, pointer)
+        } else {
+            self.plain(col, parent, lint, pointer);
+            Ok(())
+        }
+    }
+
+    fn quoted(
+        &mut self,
+        start: usize,
+        quote: char,
+        lint: bool,
+        pointer: &str,
+    ) -> Result<(), String> {
+        let content_start = start + 1;
+        let mut escaped = false;
+        let mut close = None;
+        for (idx, c) in self.source[content_start..].char_indices() {
+            match c {
+                '\\' if quote == '"' => escaped = !escaped,
+                c if c == quote && !escaped => {
+                    // Escaped single quotes are doubled.
+                    if quote == '\'' && self.source[content_start + idx + 1..].starts_with('\'') {
+                        escaped = true;
+                        continue;
+                    }
+                    close = Some(content_start + idx);
+                    break;
+                }
+                _ => escaped = false,
+            }
+        }
+        let Some(close) = close else {
+            return Err(self.error("unterminated string"));
+        };
+
+        if lint {
+            let raw = &self.source[content_start..close];
+            let mut builder = SegmentBuilder::new();
+            if quote == '"' {
+                message::push(&mut builder, raw, content_start, &unescape_double, true);
+            } else {
+                message::push(&mut builder, raw, content_start, &unescape_single, true);
+            }
+            self.finish(builder, pointer);
+        }
+        self.idx = self.lines.partition_point(|line| line.offset <= close);
+        Ok(())
+    }
+
+    fn plain(&mut self, col: usize, parent: usize, lint: bool, pointer: &str) {
+        let line = self.lines[self.idx];
+        let first = &line.text[col..];
+        let first = first
+            .find(" #")
+            .map_or(first, |idx| &first[..idx])
+            .trim_end();
+        let mut pieces = vec![(line.offset + col, first)];
+        self.idx += 1;
+
+        // Continuation lines, folded.
+        while let Some(next) = self.current() {
+            let content = next.text.trim();
+            if content.starts_with('#') || (!content.is_empty() && next.col <= parent) {
+                break;
+            }
+            if !content.is_empty() {
+                if split_key(content).is_some() || next.is_item() {
+                    break;
+                }
+                let content = content.find(" #").map_or(content, |idx| &content[..idx]);
+                pieces.push((next.offset + next.col, content.trim_end()));
+            }
+            self.idx += 1;
+        }
+
+        if !lint || (pieces.len() == 1 && is_plain_non_string(first)) {
+            return;
+        }
+        self.push_pieces(&pieces, " ", pointer);
+    }
+
+    fn block_scalar(&mut self, header: &str, parent: usize, lint: bool, pointer: &str) {
+        let literal = header.starts_with('|');
+        let explicit_indent = header[1..]
+            .chars()
+            .take_while(|c| !c.is_whitespace())
+            .find_map(|c| c.to_digit(10));
+        self.idx += 1;
+
+        let mut indent = explicit_indent.map(|indent| parent + indent as usize);
+        let mut pieces = Vec::new();
+        while let Some(next) = self.current() {
+            if next.text.trim().is_empty() {
+                self.idx += 1;
+                continue;
+            }
+            let block_indent = *indent.get_or_insert(next.col);
+            if next.col < block_indent || next.col <= parent {
+                break;
+            }
+            pieces.push((next.offset + block_indent, &next.text[block_indent..]));
+            self.idx += 1;
+        }
+        if lint {
+            self.push_pieces(&pieces, if literal { "\n" } else { " " }, pointer);
+        }
+    }
+
+    /// Push the lines of a multi-line scalar, joined by `separator`.
+    fn push_pieces(&mut self, pieces: &[(usize, &str)], separator: &str, pointer: &str) {
+        let mut builder = SegmentBuilder::new();
+        let mut previous_end = None;
+        for &(offset, text) in pieces {
+            if let Some(end) = previous_end {
+                // Blank lines are kept as line breaks.
+                let breaks = self.source[end..offset].matches('\n').count();
+                let separator = if breaks > 1 && separator == " " {
+                    "\n".repeat(breaks - 1)
+                } else {
+                    separator.repeat(breaks.max(1))
+                };
+                builder.synthetic(&separator, TextRange::new(end, offset));
+            }
+            message::push(&mut builder, text, offset, &no_unescape, true);
+            previous_end = Some(offset + text.len());
+        }
+        self.finish(builder, pointer);
+    }
+
+    fn finish(&mut self, builder: SegmentBuilder, pointer: &str) {
+        if !builder.is_empty() {
+            self.segments
+                .push(builder.finish(Some(pointer.to_string())));
+        }
+    }
+}
+
+impl Format for Yaml {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        for text in source.split_inclusive('\n') {
+            let line = text.trim_end_matches(['\n', '\r']);
+            let col = line.len() - line.trim_start_matches(' ').len();
+            lines.push(Line {
+                offset,
+                text: line,
+                col,
+            });
+            offset += text.len();
+        }
+
+        let mut parser = Parser {
+            source,
+            lines,
+            idx: 0,
+            keys: self.keys.as_deref(),
+            segments: Vec::new(),
+        };
+        parser.node(0, "")?;
+        parser.skip_blank();
+        if parser.idx < parser.lines.len() {
+            return Err(parser.error("unexpected content"));
+        }
+        Ok(parser.segments)
+    }
+
+    /// Replacements are written as is, so they must not need any quoting.
+    fn escape<'a>(&self, replacement: &'a str) -> Option<Cow<'a, str>> {
+        (!replacement.contains(['"', '\'', '\\', '\n', '#', ':']))
+            .then_some(Cow::Borrowed(replacement))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"# Ελληνικά
+el:
+  home:
+    title: Καλώς ήρθατε, {name}!  # σχόλιο
+    count: 3
+    enabled: true
+    "quoted key": "Στην \"αρχή\"\u00e9"
+    single: 'Το ''α'''
+  items:
+    - Πρώτο
+    - name: Δεύτερο
+      long: Μια μεγάλη
+        πρόταση
+  literal: |
+    Γραμμή ένα
+    Γραμμή δύο
+  folded: >-
+    Μια
+    πρόταση
+  messages: "{n, plural, one {# λέξη} other {# λέξεις}}"
+"#;
+
+    fn segments(keys: Option<&str>) -> Vec<(String, String)> {
+        let yaml = Yaml {
+            keys: keys.map(str::to_string),
+        };
+        yaml.segments(SOURCE)
+            .unwrap()
+            .into_iter()
+            .map(|segment| (segment.label.unwrap(), segment.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_segments() {
+        let segments = segments(None);
+        let expected = [
+            ("/el/home/title", "Καλώς ήρθατε, 0!"),
+            ("/el/home/quoted key", "Στην \"αρχή\"é"),
+            ("/el/home/single", "Το 'α'"),
+            ("/el/items/0", "Πρώτο"),
+            ("/el/items/1/name", "Δεύτερο"),
+            ("/el/items/1/long", "Μια μεγάλη πρόταση"),
+            ("/el/literal", "Γραμμή ένα\nΓραμμή δύο"),
+            ("/el/folded", "Μια πρόταση"),
+            ("/el/messages", "0 λέξη\n0 λέξεις"),
+        ];
+        let segments: Vec<_> = segments
+            .iter()
+            .map(|(pointer, text)| (pointer.as_str(), text.as_str()))
+            .collect();
+        assert_eq!(segments, expected);
+    }
+
+    #[test]
+    fn test_source_map() {
+        let segment = Yaml::default()
+            .segments(SOURCE)
+            .unwrap()
+            .into_iter()
+            .find(|segment| segment.label.as_deref() == Some("/el/items/1/long"))
+            .unwrap();
+        let start = segment.text.find("πρόταση").unwrap();
+        let range = segment
+            .map
+            .to_source(TextRange::new(start, start + "πρόταση".len()))
+            .unwrap();
+        assert_eq!(&SOURCE[range.start()..range.end()], "πρόταση");
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(segments(Some("/el/items/**")).len(), 3);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let yaml = Yaml::default();
+        assert!(yaml.segments("a: [b, c]\n").is_err());
+        assert!(yaml.segments("a: \"b\n").is_err());
+        assert!(yaml.segments("a:\n  b: c\n d: e\n").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Changed lines of a git repository, for `check --staged` and `--diff-base`.
+//!
+//! Files are linted as a whole, so that rules see full sentences, but only
+//! diagnostics touching changed lines are kept. With `--staged`, the staged
+//! content of the files is linted, since the line numbers are those of the
+//! index and the working tree may differ.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use grs::range::TextRange;
+
+/// Added or modified lines of a file, as 1-based inclusive ranges.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangedLines {
+    lines: Vec<(usize, usize)>,
+}
+
+impl ChangedLines {
+    /// Whether `range` of `text` is on a changed line.
+    pub fn intersects(&self, text: &str, range: TextRange) -> bool {
+        let first = text[..range.start()].matches('\n').count() + 1;
+        let inner = &text[range.start()..range.end()];
+        let last = first + inner.trim_end_matches('\n').matches('\n').count();
+        self.lines
+            .iter()
+            .any(|&(start, end)| start <= last && first <= end)
+    }
+}
+
+fn git_bytes(args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run git: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(output.stdout)
+}
+
+fn git(args: &[&str]) -> Result<String, String> {
+    String::from_utf8(git_bytes(args)?).map_err(|err| err.to_string())
+}
+
+fn repository_root() -> Result<PathBuf, String> {
+    let root = git(&["rev-parse", "--show-toplevel"])?;
+    Path::new(root.trim())
+        .canonicalize()
+        .map_err(|err| err.to_string())
+}
+
+/// Parse the output of `git diff --unified=0` into the changed lines of every
+/// file, by path relative to the repository.
+fn parse_diff(diff: &str) -> HashMap<PathBuf, ChangedLines> {
+    let mut files: HashMap<PathBuf, ChangedLines> = HashMap::new();
+    let mut current = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            current = path.strip_prefix("b/").map(PathBuf::from);
+        } else if let Some(hunk) = line.strip_prefix("@@ ")
+            && let Some(path) = &current
+        {
+            // @@ -start[,count] +start[,count] @@
+            let Some(added) = hunk.split(' ').find_map(|part| part.strip_prefix('+')) else {
+                continue;
+            };
+            let (start, count) = added.split_once(',').unwrap_or((added, "1"));
+            let (Ok(start), Ok(count)) = (start.parse::<usize>(), count.parse::<usize>()) else {
+                continue;
+            };
+            if count > 0 {
+                let lines = &mut files.entry(path.clone()).or_default().lines;
+                lines.push((start, start + count - 1));
+            }
+        }
+    }
+    files
+}
+
+/// Changed lines of the files of the repository containing the current
+/// directory, either staged or compared to `base`, by absolute path.
+///
+/// # Errors
+///
+/// Returns an error message if git fails, ex. outside of a repository.
+pub fn changed_lines(base: Option<&str>) -> Result<HashMap<PathBuf, ChangedLines>, String> {
+    let root = repository_root()?;
+    let mut args = vec![
+        "-c",
+        "core.quotePath=false",
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--unified=0",
+        "--diff-filter=ACMR",
+    ];
+    match base {
+        Some(base) => args.extend([base, "--"]),
+        None => args.push("--cached"),
+    }
+    let diff = git(&args)?;
+    Ok(parse_diff(&diff)
+        .into_iter()
+        .map(|(path, lines)| (root.join(path), lines))
+        .collect())
+}
+
+/// The staged content of `path`, a file of the repository containing the
+/// current directory.
+///
+/// # Errors
+///
+/// Returns an error message if git fails, ex. if the file is not staged.
+pub fn staged_content(path: &Path) -> Result<Vec<u8>, String> {
+    let root = repository_root()?;
+    let path = path.canonicalize().map_err(|err| err.to_string())?;
+    let relative = path
+        .strip_prefix(&root)
+        .map_err(|_| format!("{} is not in the repository", path.display()))?;
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    git_bytes(&["show", &format!(":{relative}")])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/a.txt b/a.txt
+index 1111111..2222222 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1,0 +2,2 @@ Πρώτη
++Να μην την δώσεις.
++Δεύτερη.
+@@ -5 +7 @@
+-Παλιά.
++Νέα.
+@@ -9,2 +10,0 @@
+-Σβησμένη.
+-Σβησμένη.
+diff --git a/b.md b/b.md
+new file mode 100644
+--- /dev/null
++++ b/b.md
+@@ -0,0 +1 @@
++Νέο.
+";
+
+    #[test]
+    fn test_parse_diff() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files[Path::new("a.txt")].lines, [(2, 3), (7, 7)]);
+        assert_eq!(files[Path::new("b.md")].lines, [(1, 1)]);
+    }
+
+    #[test]
+    fn test_intersects() {
+        let changed = ChangedLines {
+            lines: vec![(2, 2)],
+        };
+        let text = "α β\nγ δ\nε ζ\n";
+        let range = |word: &str| {
+            let start = text.find(word).unwrap();
+            TextRange::new(start, start + word.len())
+        };
+        assert!(!changed.intersects(text, range("β")));
+        assert!(changed.intersects(text, range("γ")));
+        assert!(changed.intersects(text, range("β\nγ")));
+        assert!(!changed.intersects(text, range("ε")));
+    }
+}
@@ -0,0 +1,198 @@
+//! The `ispell -a` pipe protocol (`grs pipe -a`), as spoken by Emacs
+//! `ispell`/`flyspell`, Vim plugins or mutt.
+//!
+//! Every input line is checked and answered with one line per word, followed
+//! by an empty line:
+//!
+//! * `*` the word is correct (omitted in terse mode),
+//! * `& <word> <count> <offset>: <suggestion>, ...` with suggestions from fixes,
+//! * `# <word> <offset>` without suggestions.
+//!
+//! Offsets are in characters, counting the `^` prefix if any. Lines starting
+//! with `^` are always checked, `!` and `%` enter and leave terse mode, `*`,
+//! `@` and `&` accept a word for the session, and the remaining commands are
+//! ignored.
+//!
+//! <https://www.gnu.org/software/emacs/manual/html_node/emacs/Spelling.html>
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+
+use itertools::Itertools;
+
+use grs::diagnostic::Diagnostic;
+use grs::linter;
+use grs::registry::Rule;
+use grs::tokenizer::tokenize;
+
+/// The version line: clients parse the ispell version out of it.
+const BANNER: &str = concat!(
+    "@(#) International Ispell Version 3.1.20 (but really grs ",
+    env!("CARGO_PKG_VERSION"),
+    ")"
+);
+
+/// The answer for a misspelled word.
+#[derive(Debug, PartialEq, Eq)]
+enum Miss<'a> {
+    Suggestions(&'a str, usize, Vec<String>),
+    NoSuggestions(&'a str, usize),
+}
+
+impl std::fmt::Display for Miss<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Suggestions(word, offset, suggestions) => write!(
+                f,
+                "& {word} {} {offset}: {}",
+                suggestions.len(),
+                suggestions.join(", ")
+            ),
+            Self::NoSuggestions(word, offset) => write!(f, "# {word} {offset}"),
+        }
+    }
+}
+
+/// Suggestions of the diagnostics touching the word at `start..end`. Fixes
+/// reaching out of the word are not suggestions for it.
+fn suggestions(text: &str, start: usize, end: usize, diagnostics: &[&Diagnostic]) -> Vec<String> {
+    diagnostics
+        .iter()
+        .flat_map(|diagnostic| &diagnostic.suggestions)
+        .map(|suggestion| &suggestion.fix)
+        .filter(|fix| start <= fix.range().start() && fix.range().end() <= end)
+        .map(|fix| {
+            format!(
+                "{}{}{}",
+                &text[start..fix.range().start()],
+                fix.replacement(text),
+                &text[fix.range().end()..end]
+            )
+        })
+        .filter(|suggestion| suggestion != &text[start..end])
+        .unique()
+        .collect()
+}
+
+/// Check a line, without its `^` prefix, whose characters start at `shift`.
+/// Returns one answer per word, `None` for the correct ones.
+fn check_line<'a>(
+    text: &'a str,
+    shift: usize,
+    rules: &[Rule],
+    accepted: &HashSet<String>,
+) -> Vec<Option<Miss<'a>>> {
+    let diagnostics = linter::check(text, rules);
+    tokenize(text)
+        .iter()
+        .filter(|token| token.is_word() || token.is_greek_word())
+        .map(|token| {
+            let range = token.range();
+            let (start, end) = (range.start(), range.end());
+            let word = &text[start..end];
+            let touching: Vec<_> = diagnostics
+                .iter()
+                .filter(|diagnostic| {
+                    diagnostic.range.start() < end && start < diagnostic.range.end()
+                })
+                .collect();
+            if touching.is_empty() || accepted.contains(word) {
+                return None;
+            }
+            let offset = shift + text[..start].chars().count();
+            let suggestions = suggestions(text, start, end, &touching);
+            Some(if suggestions.is_empty() {
+                Miss::NoSuggestions(word, offset)
+            } else {
+                Miss::Suggestions(word, offset, suggestions)
+            })
+        })
+        .collect()
+}
+
+/// Speak the protocol over stdin and stdout until stdin is closed.
+///
+/// # Errors
+///
+/// Returns an error message if stdin can not be read or stdout written.
+pub fn run(rules: &[Rule]) -> Result<(), String> {
+    let mut stdout = std::io::stdout().lock();
+    let mut terse = false;
+    let mut accepted = HashSet::new();
+
+    writeln!(stdout, "{BANNER}").map_err(|err| err.to_string())?;
+    stdout.flush().map_err(|err| err.to_string())?;
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        let (text, shift) = match line.chars().next() {
+            Some('^') => (&line[1..], 1),
+            Some('!') => {
+                terse = true;
+                continue;
+            }
+            Some('%') => {
+                terse = false;
+                continue;
+            }
+            Some('*' | '@' | '&') => {
+                accepted.insert(line[1..].to_string());
+                continue;
+            }
+            Some('#' | '~' | '+' | '-') => continue,
+            _ => (line.as_str(), 0),
+        };
+
+        for answer in check_line(text, shift, rules, &accepted) {
+            match answer {
+                Some(miss) => writeln!(stdout, "{miss}"),
+                None if terse => Ok(()),
+                None => writeln!(stdout, "*"),
+            }
+            .map_err(|err| err.to_string())?;
+        }
+        writeln!(stdout).map_err(|err| err.to_string())?;
+        stdout.flush().map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: [Rule; 3] = [
+        Rule::RemoveFinalN,
+        Rule::MonosyllableAccented,
+        Rule::MultisyllableNotAccented,
+    ];
+
+    fn answers(text: &str, shift: usize) -> Vec<String> {
+        check_line(text, shift, &RULES, &HashSet::new())
+            .into_iter()
+            .map(|answer| answer.map_or_else(|| "*".to_string(), |miss| miss.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_answers() {
+        assert_eq!(
+            answers("Να μην την δώσεις.", 0),
+            ["*", "*", "& την 1 7: τη", "*"]
+        );
+        assert_eq!(answers("Σε κανενανε!", 1), ["*", "# κανενανε 4"]);
+    }
+
+    #[test]
+    fn test_offsets_in_characters() {
+        assert_eq!(answers("😀 την δώσεις", 1)[0], "& την 1 3: τη");
+    }
+
+    #[test]
+    fn test_accepted() {
+        let accepted = HashSet::from(["κανενανε".to_string()]);
+        assert_eq!(
+            check_line("Σε κανενανε!", 0, &RULES, &accepted),
+            [None, None]
+        );
+    }
+}
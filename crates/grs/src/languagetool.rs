@@ -0,0 +1,400 @@
+//! LanguageTool compatible HTTP API (`grs serve`).
+//!
+//! Implements `/v2/check` and `/v2/languages`, so that LanguageTool clients
+//! (LibreOffice, Obsidian, browser add-ons...) can be pointed to a local grs.
+//! Offsets and lengths are in UTF-16 code units, as in LanguageTool.
+//!
+//! LanguageTool has no severities: informational matches are reported as style
+//! issues, which clients show less prominently, and every rule also carries a
+//! non-standard `severity` field.
+//!
+//! <https://languagetool.org/http-api/>
+use std::collections::HashMap;
+
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Response, Server};
+
+use grs::diagnostic::{ReportOptions, Severity};
+use grs::formats::SegmentBuilder;
+use grs::linter;
+use grs::range::TextRange;
+use grs::registry::{Rule, rule_to_code, rule_to_name};
+
+const LANGUAGE_NAME: &str = "Greek";
+const LANGUAGE_CODE: &str = "el";
+const LANGUAGE_LONG_CODE: &str = "el-GR";
+
+/// Number of characters shown around a match in its context.
+const CONTEXT_SIZE: usize = 40;
+
+/// Decode `%XX` sequences.
+pub fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| std::str::from_utf8(tail.get(..2)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Parse an `application/x-www-form-urlencoded` string.
+fn parse_form(form: &str) -> HashMap<String, String> {
+    form.split('&')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = percent_decode(&key.replace('+', " "))?;
+            let value = percent_decode(&value.replace('+', " "))?;
+            Some((key, value))
+        })
+        .collect()
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn parse_rules(codes: Option<&String>) -> Result<Vec<Rule>, String> {
+    codes
+        .into_iter()
+        .flat_map(|codes| codes.split(','))
+        .filter(|code| !code.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+fn issue_type(rule: Rule, severity: Severity) -> &'static str {
+    const TYPOGRAPHICAL: [Rule; 4] = [
+        Rule::Punctuation,
+        Rule::MixedScripts,
+        Rule::AmbiguousChar,
+        Rule::ForbiddenChar,
+    ];
+    if severity == Severity::Info {
+        "style"
+    } else if rule == Rule::DuplicatedWord {
+        "duplication"
+    } else if TYPOGRAPHICAL.contains(&rule) {
+        "typographical"
+    } else {
+        "misspelling"
+    }
+}
+
+/// The document of a check request: its source, as seen by the client, and the
+/// prose to check. Markup is replaced by its `interpretAs` value, if any.
+fn document(params: &HashMap<String, String>) -> Result<(String, grs::formats::Segment), String> {
+    let mut builder = SegmentBuilder::new();
+    if let Some(text) = params.get("text") {
+        builder.verbatim(text, 0);
+        return Ok((text.clone(), builder.finish(None)));
+    }
+    let Some(data) = params.get("data") else {
+        return Err("missing 'text' or 'data' parameter".to_string());
+    };
+
+    let data: Value = serde_json::from_str(data).map_err(|err| format!("invalid 'data': {err}"))?;
+    let annotation = data
+        .get("annotation")
+        .and_then(Value::as_array)
+        .ok_or("invalid 'data': missing 'annotation'")?;
+    let mut source = String::new();
+    for part in annotation {
+        let start = source.len();
+        if let Some(text) = part.get("text").and_then(Value::as_str) {
+            source.push_str(text);
+            builder.verbatim(text, start);
+        } else if let Some(markup) = part.get("markup").and_then(Value::as_str) {
+            source.push_str(markup);
+            let interpret_as = part
+                .get("interpretAs")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            builder.synthetic(interpret_as, TextRange::new(start, source.len()));
+        } else {
+            return Err("invalid 'data': parts need 'text' or 'markup'".to_string());
+        }
+    }
+    Ok((source, builder.finish(None)))
+}
+
+/// Answer a `/v2/check` request.
+fn check(
+    params: &HashMap<String, String>,
+    default_rules: &[Rule],
+    report: &ReportOptions,
+) -> Result<Value, String> {
+    let language = params.get("language").map_or("auto", String::as_str);
+    if !matches!(language, "auto" | LANGUAGE_CODE | LANGUAGE_LONG_CODE) {
+        return Err(format!("unsupported language: {language}"));
+    }
+
+    let enabled = parse_rules(params.get("enabledRules"))?;
+    let disabled = parse_rules(params.get("disabledRules"))?;
+    let mut rules = if params
+        .get("enabledOnly")
+        .is_some_and(|value| value == "true")
+    {
+        enabled
+    } else {
+        default_rules.iter().copied().chain(enabled).collect()
+    };
+    rules.retain(|rule| !disabled.contains(rule));
+
+    let (source, segment) = document(params)?;
+    let matches: Vec<Value> = linter::check(&segment.text, &rules)
+        .into_iter()
+        .map(|diagnostic| {
+            // Replacements apply to the match, so the match covers the
+            // diagnostic and the ranges of every suggestion.
+            let fixes: Vec<_> = diagnostic
+                .suggestions
+                .iter()
+                .map(|suggestion| &suggestion.fix)
+                .collect();
+            let covered = fixes.iter().fold(diagnostic.range, |covered, fix| {
+                TextRange::new(
+                    covered.start().min(fix.range().start()),
+                    covered.end().max(fix.range().end()),
+                )
+            });
+            let mapped = if fixes.is_empty() {
+                None
+            } else {
+                segment.map.to_source(covered).map(|range| (covered, range))
+            };
+            let range = mapped.map_or_else(
+                || segment.map.to_source_lossy(diagnostic.range),
+                |(_, range)| range,
+            );
+            let replacements: Vec<Value> = match mapped {
+                Some((covered, _)) => fixes
+                    .iter()
+                    .map(|fix| {
+                        let range = fix.range();
+                        let value = format!(
+                            "{}{}{}",
+                            &segment.text[covered.start()..range.start()],
+                            fix.replacement(&segment.text),
+                            &segment.text[range.end()..covered.end()]
+                        );
+                        json!({ "value": value })
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            let before: String = {
+                let chars: Vec<char> = source[..range.start()]
+                    .chars()
+                    .rev()
+                    .take(CONTEXT_SIZE)
+                    .collect();
+                chars.into_iter().rev().collect()
+            };
+            let matched = &source[range.start()..range.end()];
+            let after: String = source[range.end()..].chars().take(CONTEXT_SIZE).collect();
+
+            let code = rule_to_code(diagnostic.kind);
+            let name = rule_to_name(diagnostic.kind);
+            let severity = report.severities.get(diagnostic.kind);
+            json!({
+                "message": diagnostic.message.get(report.lang),
+                "shortMessage": "",
+                "replacements": replacements,
+                "offset": utf16_len(&source[..range.start()]),
+                "length": utf16_len(matched),
+                "context": {
+                    "text": format!("{before}{matched}{after}"),
+                    "offset": utf16_len(&before),
+                    "length": utf16_len(matched),
+                },
+                "sentence": format!("{before}{matched}{after}"),
+                "type": { "typeName": "Other" },
+                "rule": {
+                    "id": code,
+                    "description": name,
+                    "issueType": issue_type(diagnostic.kind, severity),
+                    "category": { "id": "GRS", "name": "grs" },
+                    "severity": severity.name(),
+                },
+                "ignoreForIncompleteSentence": false,
+                "contextForSureMatch": 0,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "software": {
+            "name": "grs",
+            "version": env!("CARGO_PKG_VERSION"),
+            "apiVersion": 1,
+            "premium": false,
+            "status": "",
+        },
+        "language": {
+            "name": LANGUAGE_NAME,
+            "code": LANGUAGE_LONG_CODE,
+            "detectedLanguage": {
+                "name": LANGUAGE_NAME,
+                "code": LANGUAGE_LONG_CODE,
+                "confidence": 1.0,
+            },
+        },
+        "matches": matches,
+    }))
+}
+
+fn languages() -> Value {
+    json!([{ "name": LANGUAGE_NAME, "code": LANGUAGE_CODE, "longCode": LANGUAGE_LONG_CODE }])
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+/// Serve the API on `host:port` until the process is killed.
+///
+/// # Errors
+///
+/// Returns an error message if the server can not be started.
+pub fn run(
+    host: &str,
+    port: u16,
+    default_rules: &[Rule],
+    report: &ReportOptions,
+) -> Result<(), String> {
+    let server = Server::http((host, port)).map_err(|err| err.to_string())?;
+    println!("Listening on http://{host}:{port}/v2/");
+
+    for mut request in server.incoming_requests() {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let mut params = parse_form(query);
+        let mut body = String::new();
+        if request.as_reader().read_to_string(&mut body).is_ok() {
+            params.extend(parse_form(&body));
+        }
+
+        let (status, body) = match (request.method(), path) {
+            (Method::Options, _) => (204, String::new()),
+            (Method::Get | Method::Post, "/v2/check") => {
+                match check(&params, default_rules, report) {
+                    Ok(response) => (200, response.to_string()),
+                    Err(err) => (400, err),
+                }
+            }
+            (Method::Get, "/v2/languages") => (200, languages().to_string()),
+            _ => (404, "not found".to_string()),
+        };
+        let content_type = if status == 200 {
+            "application/json"
+        } else {
+            "text/plain; charset=utf-8"
+        };
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header("Content-Type", content_type))
+            .with_header(header("Access-Control-Allow-Origin", "*"))
+            .with_header(header("Access-Control-Allow-Headers", "*"));
+        if let Err(err) = request.respond(response) {
+            eprintln!("Failed to respond: {err}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(params: &[(&str, &str)]) -> Vec<Value> {
+        let params = params
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        let rules = [Rule::RemoveFinalN, Rule::MixedScripts];
+        let response = check(&params, &rules, &ReportOptions::default()).unwrap();
+        response["matches"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn test_parse_form() {
+        let form = parse_form("text=%CE%B1+%CE%B2&language=el");
+        assert_eq!(form["text"], "α β");
+        assert_eq!(form["language"], "el");
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        let matches = matches(&[("text", "😀 στην δόξα")]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0]["offset"], 3);
+        assert_eq!(matches[0]["length"], 4);
+        assert_eq!(matches[0]["replacements"][0]["value"], "στη");
+        assert_eq!(matches[0]["rule"]["id"], "RFN");
+        assert_eq!(matches[0]["rule"]["severity"], "warning");
+        assert_eq!(matches[0]["rule"]["issueType"], "misspelling");
+        assert_eq!(
+            matches[0]["message"],
+            "final -ν should be omitted before «δ»"
+        );
+    }
+
+    #[test]
+    fn test_alternative_replacements() {
+        let matches = matches(&[
+            ("text", "το λάθος λάθος"),
+            ("enabledRules", "DW"),
+            ("enabledOnly", "true"),
+        ]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0]["offset"], "το ".chars().count());
+        assert_eq!(matches[0]["replacements"][0]["value"], "λάθος");
+        assert_eq!(matches[0]["replacements"][1]["value"], "λάθος, λάθος");
+    }
+
+    #[test]
+    fn test_annotation() {
+        let data = r#"{"annotation": [
+            {"markup": "<p>"},
+            {"text": "Aλλά "},
+            {"markup": "<b>", "interpretAs": ""},
+            {"text": "στην δόξα"}
+        ]}"#;
+        let matches = matches(&[("data", data)]);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0]["offset"], 3);
+        assert_eq!(matches[1]["offset"], "<p>Aλλά <b>".chars().count());
+    }
+
+    #[test]
+    fn test_rule_selection() {
+        let text = ("text", "Aλλά στην δόξα");
+        assert_eq!(matches(&[text, ("disabledRules", "RFN")]).len(), 1);
+        let enabled_only = [text, ("enabledRules", "MS"), ("enabledOnly", "true")];
+        assert_eq!(matches(&enabled_only)[0]["rule"]["id"], "MS");
+    }
+
+    #[test]
+    fn test_errors() {
+        let params = HashMap::from([("text".to_string(), "α".to_string())]);
+        let report = ReportOptions::default();
+        assert!(check(&params, &[], &report).is_ok());
+        let params = HashMap::from([("language".to_string(), "fr".to_string())]);
+        assert!(check(&params, &[], &report).is_err());
+        assert!(check(&HashMap::new(), &[], &report).is_err());
+    }
+}
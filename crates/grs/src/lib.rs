@@ -1,7 +1,10 @@
 pub mod cli;
 pub mod diagnostic;
+pub mod encoding;
+pub mod formats;
 pub mod range;
 pub mod registry;
+pub mod settings;
 pub mod text_diff;
 pub mod tokenizer;
 
@@ -1,120 +1,111 @@
 //! Named linter for archaic reasons: it should be a checker.
 use colored::Colorize;
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::diagnostic::{Diagnostic, Fix};
+use crate::diagnostic::{Diagnostic, Edit, Fix, ReportOptions, Severity};
 use crate::range::TextRange;
-use crate::registry::Rule;
+use crate::registry::{LintRule, Phase, Rule};
 use crate::tokenizer::{Doc, Token, tokenize};
 
-#[allow(clippy::wildcard_imports)]
-use crate::rules::*;
+pub type Config<'a> = &'a [Rule];
 
-type Config<'a> = &'a [Rule];
+/// Sentence final punctuation, including the Greek question mark.
+const SENTENCE_END: [char; 6] = ['.', ';', '\u{37E}', '!', '?', '…'];
 
-fn check_token_with_context<'a>(
-    token: &Token<'a>,
-    doc: &Doc<'a>,
-    config: Config,
-) -> Vec<Diagnostic> {
-    let mut diagnostics = vec![];
-
-    if config.contains(&Rule::MonosyllableAccented) {
-        monosyllable_accented(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::MissingAccentCapital) {
-        missing_accent_capital(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::MultisyllableNotAccented) {
-        multisyllable_not_accented(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::MissingDoubleAccents) {
-        missing_double_accents(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::AddFinalN) {
-        add_final_n(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::RemoveFinalN) {
-        remove_final_n(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::DuplicatedWord) {
-        duplicated_word(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::ForbiddenAccent) {
-        forbidden_accent(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::ForbiddenDoubleAccent) {
-        forbidden_double_accent(token, doc, &mut diagnostics);
-    }
-    if config.contains(&Rule::Punctuation) {
-        punctuation(token, doc, &mut diagnostics);
-    }
+/// Whether a token ends a sentence, ex. `.` or `;»` but not `3.5`.
+fn is_sentence_end(token: &Token) -> bool {
+    token.is_punctuation()
+        && token
+            .text()
+            .trim_end_matches(['»', '"', '”', '’', '\'', ')', ']'])
+            .ends_with(SENTENCE_END)
+}
 
-    diagnostics
+/// The rules of a config, by phase.
+///
+/// Computed once per config, so that checking a text only calls the rules of
+/// each phase.
+pub struct RuleSet {
+    raw: Vec<&'static dyn LintRule>,
+    token: Vec<&'static dyn LintRule>,
+    sentence: Vec<&'static dyn LintRule>,
+    document: Vec<&'static dyn LintRule>,
 }
 
-fn check_raw(text: &str, config: Config) -> Vec<Diagnostic> {
-    let mut diagnostics = vec![];
-    if config.contains(&Rule::OutdatedSpelling) {
-        outdated_spelling(text, &mut diagnostics);
-    }
-    if config.contains(&Rule::AmbiguousChar) {
-        ambiguous_char(text, &mut diagnostics);
+impl RuleSet {
+    pub fn new(config: Config) -> Self {
+        let phase = |phase: Phase| {
+            config
+                .iter()
+                .filter(|rule| rule.phase() == phase)
+                .map(Rule::checker)
+                .collect()
+        };
+        Self {
+            raw: phase(Phase::Raw),
+            token: phase(Phase::Token),
+            sentence: phase(Phase::Sentence),
+            document: phase(Phase::Document),
+        }
     }
-    if config.contains(&Rule::ForbiddenChar) {
-        forbidden_char(text, &mut diagnostics);
+
+    const fn requires_tokenizing(&self) -> bool {
+        !(self.token.is_empty() && self.sentence.is_empty() && self.document.is_empty())
     }
-    diagnostics
-}
 
-pub fn check(text: &str, config: Config) -> Vec<Diagnostic> {
-    let mut diagnostics = Vec::new();
+    pub fn check(&self, text: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
 
-    // Raw replacements that need no tokenizing.
-    diagnostics.extend(check_raw(text, config));
+        // Raw replacements that need no tokenizing.
+        for rule in &self.raw {
+            rule.check_raw(text, &mut diagnostics);
+        }
 
-    let rules_requiring_doc: Vec<_> = config
-        .iter()
-        .copied()
-        .filter(super::registry::Rule::requires_tokenizing)
-        .collect();
+        if self.requires_tokenizing() {
+            self.check_doc(&tokenize(text), &mut diagnostics);
+        }
 
-    // Early exit if we do not need tokenizing.
-    if rules_requiring_doc.is_empty() {
-        return diagnostics;
+        // In the order of the text, whatever their phase.
+        diagnostics.sort_by_key(|diagnostic| diagnostic.range.start());
+        diagnostics
     }
 
-    let doc = tokenize(text);
+    fn check_doc(&self, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        // Run the token-context-based rules.
+        if !self.token.is_empty() {
+            for token in doc.iter().filter(|token| token.is_greek_word()) {
+                for rule in &self.token {
+                    rule.check_token(token, doc, diagnostics);
+                }
+            }
+        }
 
-    // Run the token-context-based rules.
-    for token in &doc {
-        // A match should be better.
-        if token.is_whitespace() || token.is_punctuation() {
-            // No rules at the moment concern these two
-        } else if token.is_greek_word() {
-            diagnostics.extend(check_token_with_context(token, &doc, &rules_requiring_doc));
-        } else {
-            // Does not use doc
-            if rules_requiring_doc.contains(&Rule::MixedScripts) {
-                mixed_scripts(token, &doc, &mut diagnostics);
+        if !self.sentence.is_empty() {
+            for sentence in doc.split_inclusive(is_sentence_end) {
+                for rule in &self.sentence {
+                    rule.check_sentence(sentence, doc, diagnostics);
+                }
             }
         }
+
+        for rule in &self.document {
+            rule.check_document(doc, diagnostics);
+        }
     }
+}
 
-    diagnostics
+pub fn check(text: &str, config: Config) -> Vec<Diagnostic> {
+    RuleSet::new(config).check(text)
 }
 
-/// Compare two fixes.
+/// Compare the priority of two fixes, the first ones winning conflicts.
 fn cmp_fix(rule1: Rule, rule2: Rule, fix1: &Fix, fix2: &Fix) -> std::cmp::Ordering {
     // Always apply `DuplicatedWords` at the start
-    match (rule1, rule2) {
-        (Rule::DuplicatedWord, _) => std::cmp::Ordering::Less,
-        (_, Rule::DuplicatedWord) => std::cmp::Ordering::Greater,
-        _ => std::cmp::Ordering::Equal,
-    }
-    // Apply fixes in Ascending order of their start position.
-    .then_with(|| fix1.range.start().cmp(&fix2.range.start()))
+    (rule2 == Rule::DuplicatedWord)
+        .cmp(&(rule1 == Rule::DuplicatedWord))
+        // Apply fixes in Ascending order of their start position.
+        .then_with(|| fix1.range().start().cmp(&fix2.range().start()))
 }
 
 /// Get sentence context to print for visualization.
@@ -195,153 +186,555 @@ fn get_context_message(text: &str, range: &TextRange) -> String {
     .to_string()
 }
 
-fn get_rich_context_message(text: &str, range: &TextRange, rule: Rule) -> String {
-    let ctx = get_context_message(text, range);
+pub(crate) fn get_rich_context_message(
+    text: &str,
+    diagnostic: &Diagnostic,
+    report: &ReportOptions,
+) -> String {
+    let rule = diagnostic.kind;
+    let severity = report.severities.get(rule);
+    let ctx = get_context_message(text, &diagnostic.range);
     let fixable = if rule.has_fix() {
         format!("[{}]", "*".to_string().cyan())
     } else {
         "   ".to_string()
     };
-    let message = format!("{:<3}: {} {}", format!("{rule}").cyan(), fixable, ctx);
+    let label = format!("{:<7}", severity.name());
+    let label = match severity {
+        Severity::Error => label.red(),
+        Severity::Warning => label.yellow(),
+        Severity::Info => label.blue(),
+    };
+    let message = format!(
+        "{:<3}: {} {} {} ({})",
+        format!("{rule}").cyan(),
+        label,
+        fixable,
+        ctx,
+        diagnostic.message.get(report.lang).italic()
+    );
     message
 }
 
-const MAX_ITERATIONS: usize = 100;
+/// The maximum number of fix passes, as a safeguard.
+const MAX_PASSES: usize = 100;
 
-type Counter = HashMap<Rule, usize>;
+pub type Counter = HashMap<Rule, usize>;
+
+/// A fix applied by [`fix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedFix {
+    pub rule: Rule,
+    /// The range of the fix in the original text. A fix of text written by a
+    /// previous pass has the range of the text it replaced.
+    pub range: TextRange,
+    /// The pass of the fix, from 1.
+    pub pass: usize,
+}
+
+/// Why fixing a text did not end on a stable text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixError {
+    /// The text came back to its state after `pass`: the fixes of `rules`
+    /// undo each other.
+    Cycle { rules: Vec<Rule>, pass: usize },
+    /// There were still fixes to apply after the maximum number of passes.
+    TooManyPasses(usize),
+}
+
+impl std::fmt::Display for FixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Cycle { rules, pass } => write!(
+                f,
+                "the fixes of {} undo each other (the text of pass {pass} came back)",
+                rules.iter().join(", ")
+            ),
+            Self::TooManyPasses(passes) => {
+                write!(f, "the text is not stable after {passes} passes")
+            }
+        }
+    }
+}
+
+/// The result of [`fix`].
+#[derive(Debug)]
+pub struct FixReport {
+    /// The fixed text.
+    pub text: String,
+    /// The applied fixes, in order.
+    pub applied: Vec<AppliedFix>,
+    /// The number of passes that applied fixes.
+    pub passes: usize,
+    /// The diagnostics left unfixed, with ranges in the fixed text.
+    pub remaining: Vec<Diagnostic>,
+    /// Set if fixing stopped before the text was stable.
+    pub error: Option<FixError>,
+}
+
+impl FixReport {
+    /// The number of applied fixes per rule.
+    pub fn counter(&self) -> Counter {
+        let mut counter = Counter::new();
+        for applied in &self.applied {
+            *counter.entry(applied.rule).or_insert(0) += 1;
+        }
+        counter
+    }
+
+    /// The original ranges of the applied fixes of `rule`.
+    pub fn ranges(&self, rule: Rule) -> impl Iterator<Item = TextRange> + '_ {
+        self.applied
+            .iter()
+            .filter(move |applied| applied.rule == rule)
+            .map(|applied| applied.range)
+    }
+}
 
 /// Repeatedly fix text until stable.
 //
-// Should return result
-//
 // cf
 // ruff_linter/src/linter.rs::lint_fix
 // https://github.com/astral-sh/ruff/blob/main/crates/ruff_linter/src/linter.rs
 //
 // ruff_linter/src/fix/mod.rs
 // https://github.com/astral-sh/ruff/blob/main/crates/ruff_linter/src/fix/mod.rs
-//
-// NOTE:
-// * Should do statistics always, for safety // and it's cheap
-// * Uses rules with no fixes. We should remove those from the config
-//   since they are not printed nor, obviously, fixable.
-pub fn fix(text: &str, config: Config) -> (String, Vec<String>, Counter) {
-    let mut transformed = text.to_string();
-    // For debugging. To remove eventually.
-    #[allow(unused_mut)]
-    let mut messages = Vec::new();
-    let mut fixed = Counter::new();
-    let mut iterations = 0;
-
-    // These rules have no fixes: remove them from the config.
-    // TODO: do this before reaching this function
-    let rules_with_fixes = config
-        .iter()
-        .copied()
-        .filter(super::registry::Rule::has_fix)
-        .collect::<Vec<_>>();
-    let config: Config = &rules_with_fixes;
+pub fn fix(text: &str, config: Config) -> FixReport {
+    fix_impl(text, config, |text, rules| rules.check(text))
+}
 
-    // This is potentially a bad idea iif a fix could affect previous tokens,
-    // which is possible but rare since there is not much dependency across tokens.
-    //
-    // The whole idea is to store the unchanged prefix, where we found no errors,
-    // so that we do not have to re-tokenize it on the next pass.
-    //
-    // Note that the behaviour can be controlled simply by setting first_fix to false:
-    // that will re-tokenize the transformed string at each pass.
-    let mut final_transformed = String::with_capacity(text.len());
+/// Repeatedly fix text until stable, using a custom `check` function.
+///
+/// The function is called on the whole text at every pass, with the rules to
+/// check, and must return diagnostics whose fix ranges refer to it. This is
+/// what structured formats use to only fix the prose of a document.
+///
+/// The passes only check the rules with fixes, the remaining diagnostics are
+/// those of every rule of `config`.
+pub fn fix_with<F>(text: &str, config: Config, check: F) -> FixReport
+where
+    F: Fn(&str, &RuleSet) -> Vec<Diagnostic>,
+{
+    fix_impl(text, config, check)
+}
 
-    loop {
-        let mut last_pos: Option<usize> = None;
+/// Like [`fix`], but only apply the fixes of the diagnostics whose range is kept.
+///
+/// `keep` is called with the text of the current pass.
+pub fn fix_filtered<F>(text: &str, config: Config, keep: F) -> FixReport
+where
+    F: Fn(&str, TextRange) -> bool,
+{
+    fix_with(text, config, |text, rules| {
+        let mut diagnostics = rules.check(text);
+        diagnostics.retain(|diagnostic| keep(text, diagnostic.range));
+        diagnostics
+    })
+}
 
-        let diagnostics = check(&transformed, config);
+/// Select the fixes to apply in one pass: by priority, every fix whose edits
+/// neither overlap nor touch those of the fixes already selected.
+///
+/// The others are left for the next pass, where they are checked again against
+/// the fixed text.
+fn select_fixes<'a, I>(fixes: I) -> Vec<(Rule, &'a Fix)>
+where
+    I: IntoIterator<Item = (Rule, &'a Fix)>,
+{
+    // Ranges of the selected edits, by start. They are disjoint, so only the
+    // last one starting before the end of an edit may reach it.
+    let mut taken: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut selected = Vec::new();
+    for (rule, fix) in fixes
+        .into_iter()
+        .sorted_by(|(rule1, fix1), (rule2, fix2)| cmp_fix(*rule1, *rule2, fix1, fix2))
+    {
+        let conflicts = fix.edits().iter().any(|edit| {
+            taken
+                .range(..=edit.range.end())
+                .next_back()
+                .is_some_and(|(_, &end)| end >= edit.range.start())
+        });
+        if conflicts {
+            continue;
+        }
+        for edit in fix.edits() {
+            taken.insert(edit.range.start(), edit.range.end());
+        }
+        selected.push((rule, fix));
+    }
+    selected
+}
 
-        // Select diagnostics that can be fixed
-        let mut with_fixes = diagnostics
-            .iter()
-            .filter(|diagnostic| diagnostic.fix.is_some())
-            .peekable();
-        // And exit if there are none
-        if with_fixes.peek().is_none() {
+/// Apply non-overlapping `edits`, sorted by start, to `text`.
+fn apply_edits(text: &str, edits: &[Edit]) -> String {
+    let mut fixed = String::with_capacity(text.len());
+    let mut last = 0;
+    for edit in edits {
+        fixed.push_str(&text[last..edit.range.start()]);
+        fixed.push_str(&edit.content);
+        last = edit.range.end();
+    }
+    fixed.push_str(&text[last..]);
+    fixed
+}
+
+/// Map an offset of a text fixed with `edits` back to the text before them.
+///
+/// An offset inside the content of an edit maps to the start of its range, or
+/// to its end for the `end` of a range.
+fn map_offset(edits: &[Edit], offset: usize, end: bool) -> usize {
+    // The ends of the last edit, before and after fixing.
+    let (mut old, mut new) = (0, 0);
+    for edit in edits {
+        let start = new + edit.range.start() - old;
+        let content_end = start + edit.content.len();
+        if offset < start || (end && offset == start) {
             break;
         }
+        if offset < content_end {
+            return if end {
+                edit.range.end()
+            } else {
+                edit.range.start()
+            };
+        }
+        (old, new) = (edit.range.end(), content_end);
+    }
+    old + offset - new
+}
 
-        // println!(
-        //     "{}",
-        //     format!(
-        //         "At iteration {}. {} diagnostics. {} with_fixes. {} text length.",
-        //         iterations,
-        //         diagnostics.len().to_string().red().bold(),
-        //         with_fixes.clone().count().to_string().red().bold(),
-        //         transformed.len().to_string().red().bold(),
-        //     )
-        //     .italic()
-        // );
-
-        let rfixes = with_fixes
-            .filter_map(|diagnostic| diagnostic.fix.as_ref().map(|fix| (diagnostic.kind, fix)))
-            .sorted_by(|(rule1, fix1), (rule2, fix2)| cmp_fix(*rule1, *rule2, fix1, fix2));
-
-        let mut first_fix = true;
-        let mut transformed_this_iter = String::with_capacity(transformed.len());
-
-        for (rule, fix) in rfixes {
-            // May happen if we push some rules due to priority
-            // We skip this fix.
-            if let Some(last_pos) = last_pos
-                && last_pos > fix.range.start()
-            {
-                eprintln!("Break due to disordered fixes");
-                break;
-            }
+/// Map a range of the text after the passes of `history` back to the original.
+fn map_range(history: &[Vec<Edit>], range: TextRange) -> TextRange {
+    history.iter().rev().fold(range, |range, edits| {
+        let start = map_offset(edits, range.start(), false);
+        // An empty range where text was deleted stays after it.
+        let end = map_offset(edits, range.end(), true).max(start);
+        TextRange::new(start, end)
+    })
+}
 
-            *fixed.entry(rule).or_insert(0) += 1;
+fn hash_text(text: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
 
-            if first_fix {
-                final_transformed.push_str(&transformed[last_pos.unwrap_or(0)..fix.range.start()]);
-                final_transformed.push_str(&fix.replacement);
-                first_fix = false;
-            } else {
-                transformed_this_iter
-                    .push_str(&transformed[last_pos.unwrap_or(0)..fix.range.start()]);
-                transformed_this_iter.push_str(&fix.replacement);
-            }
+/// Apply the edits of every pass of `history` to the original `text`.
+fn replay(text: &str, history: &[Vec<Edit>]) -> String {
+    history
+        .iter()
+        .fold(text.to_string(), |text, edits| apply_edits(&text, edits))
+}
 
-            last_pos = Some(fix.range.end());
+fn fix_impl<F>(text: &str, config: Config, check: F) -> FixReport
+where
+    F: Fn(&str, &RuleSet) -> Vec<Diagnostic>,
+{
+    let mut transformed = text.to_string();
+    let mut applied = Vec::new();
+    // The edits of every pass, to map ranges back to the original text.
+    let mut history: Vec<Vec<Edit>> = Vec::new();
+    // The passes after which each text was seen, by hash, to detect cycles.
+    // Only the hashes are kept: on a match, the earlier text is rebuilt from
+    // the edits to rule out a collision.
+    let mut states: HashMap<u64, Vec<usize>> = HashMap::from([(hash_text(text), vec![0])]);
+
+    // The passes only need the rules with fixes.
+    let rules_with_fixes = config
+        .iter()
+        .copied()
+        .filter(Rule::has_fix)
+        .collect::<Vec<_>>();
+    let fixable = RuleSet::new(&rules_with_fixes);
+
+    // The diagnostics of the last pass, if they are those of the fixed text.
+    let (last, error) = loop {
+        let diagnostics = check(&transformed, &fixable);
+        let selected = select_fixes(
+            diagnostics
+                .iter()
+                .filter_map(|diagnostic| Some((diagnostic.kind, diagnostic.fix()?))),
+        );
+        // Exit if there is nothing left to fix.
+        if selected.is_empty() {
+            break (Some(diagnostics), None);
         }
-
-        if let Some(last_pos) = last_pos {
-            // May not be true if the text is composed of only one word: "Χωρίς\n"
-            // assert!(last_pos < transformed.len());
-            transformed_this_iter.push_str(&transformed[last_pos..]);
+        if history.len() == MAX_PASSES {
+            break (Some(diagnostics), Some(FixError::TooManyPasses(MAX_PASSES)));
         }
 
-        transformed = transformed_this_iter;
-
-        iterations += 1;
-        if iterations == MAX_ITERATIONS {
-            eprintln!("Warning: exceeded maximum iterations in fix.");
-            break;
+        let pass = history.len() + 1;
+        for (rule, fix) in &selected {
+            applied.push(AppliedFix {
+                rule: *rule,
+                range: map_range(&history, fix.range()),
+                pass,
+            });
         }
-    }
+        let edits = selected
+            .iter()
+            .flat_map(|(_, fix)| fix.edits())
+            .cloned()
+            .sorted_by_key(|edit| edit.range.start())
+            .collect::<Vec<_>>();
+        transformed = apply_edits(&transformed, &edits);
+        history.push(edits);
+
+        let seen = states.entry(hash_text(&transformed)).or_default();
+        let previous = seen
+            .iter()
+            .copied()
+            .find(|&previous| replay(text, &history[..previous]) == transformed);
+        seen.push(pass);
+        if let Some(previous) = previous {
+            let rules = applied
+                .iter()
+                .filter(|applied| applied.pass > previous)
+                .map(|applied| applied.rule)
+                .unique()
+                .collect();
+            let error = FixError::Cycle {
+                rules,
+                pass: previous,
+            };
+            break (None, Some(error));
+        }
+    };
 
-    final_transformed.push_str(&transformed);
+    let remaining = match last {
+        Some(diagnostics) if rules_with_fixes.len() == config.len() => diagnostics,
+        _ => check(&transformed, &RuleSet::new(config)),
+    };
 
-    (final_transformed, messages, fixed)
+    FixReport {
+        text: transformed,
+        applied,
+        passes: history.len(),
+        remaining,
+        error,
+    }
 }
 
 // https://github.com/astral-sh/ruff/blob/fc59e1b17f0a538a0150ea5a63de6305a8810c62/crates/ruff_linter/src/linter.rs#L382
-pub fn lint_only(text: &str, config: Config) -> (Vec<String>, Counter) {
+pub fn lint_only(text: &str, config: Config, report: &ReportOptions) -> (Vec<String>, Counter) {
+    lint_only_filtered(text, config, report, |_| true)
+}
+
+/// Like [`lint_only`], but only report the diagnostics whose range is kept.
+pub fn lint_only_filtered<F>(
+    text: &str,
+    config: Config,
+    report: &ReportOptions,
+    keep: F,
+) -> (Vec<String>, Counter)
+where
+    F: Fn(TextRange) -> bool,
+{
     let diagnostics = check(text, config);
     let mut statistics = Counter::new();
     let messages = diagnostics
         .iter()
+        .filter(|diagnostic| keep(diagnostic.range))
         .map(|diagnostic| {
             *statistics.entry(diagnostic.kind).or_insert(0) += 1;
-            get_rich_context_message(text, &diagnostic.range, diagnostic.kind)
+            get_rich_context_message(text, diagnostic, report)
         })
         .collect();
 
     (messages, statistics)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::{Message, Suggestion};
+
+    fn replace(start: usize, end: usize, content: &str) -> Edit {
+        Edit::replacement(content.to_string(), TextRange::new(start, end))
+    }
+
+    fn diagnostic(rule: Rule, fix: Fix) -> Diagnostic {
+        Diagnostic {
+            kind: rule,
+            range: fix.range(),
+            suggestions: vec![Suggestion::preferred(
+                Message::new(String::new(), String::new()),
+                fix,
+            )],
+            severity: rule.severity(),
+            message: Message::new(String::new(), String::new()),
+        }
+    }
+
+    #[test]
+    fn test_select_overlapping() {
+        let first = Fix::edit(replace(0, 4, "α"));
+        let second = Fix::edit(replace(2, 6, "β"));
+        let third = Fix::edit(replace(8, 9, "γ"));
+        let selected = select_fixes([
+            (Rule::MixedScripts, &second),
+            (Rule::MixedScripts, &third),
+            (Rule::MonosyllableAccented, &first),
+        ]);
+        // By position: the second fix overlaps the first one.
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].1, &first);
+        assert_eq!(selected[1].1, &third);
+    }
+
+    #[test]
+    fn test_select_touching() {
+        let word = Fix::edit(replace(0, 4, "αβ"));
+        let insertion = Fix::edit(Edit::insertion("ν".to_string(), 4));
+        let selected = select_fixes([(Rule::MixedScripts, &word), (Rule::AddFinalN, &insertion)]);
+        assert_eq!(selected, [(Rule::MixedScripts, &word)]);
+    }
+
+    #[test]
+    fn test_select_priority() {
+        let later = Fix::edit(Edit::deletion(TextRange::new(4, 10)));
+        let earlier = Fix::edit(replace(0, 6, "α"));
+        let selected = select_fixes([
+            (Rule::MonosyllableAccented, &earlier),
+            (Rule::DuplicatedWord, &later),
+        ]);
+        // Duplicated words come first, whatever their position.
+        assert_eq!(selected, [(Rule::DuplicatedWord, &later)]);
+    }
+
+    #[test]
+    fn test_apply_interleaved() {
+        // Edits of two fixes may alternate, as long as they do not touch.
+        let text = "abcdefgh";
+        let first = Fix::new(vec![replace(0, 1, "A"), replace(6, 7, "G")]);
+        let second = Fix::edit(replace(3, 4, "D"));
+        let selected = select_fixes([(Rule::MixedScripts, &first), (Rule::AmbiguousChar, &second)]);
+        assert_eq!(selected.len(), 2);
+        let edits = selected
+            .iter()
+            .flat_map(|(_, fix)| fix.edits())
+            .cloned()
+            .sorted_by_key(|edit| edit.range.start())
+            .collect::<Vec<_>>();
+        assert_eq!(apply_edits(text, &edits), "AbcDefGh");
+    }
+
+    #[test]
+    fn test_deferred_fixes() {
+        // Two fixes touching each other: one per pass.
+        let check = |text: &str, _: &RuleSet| {
+            let mut diagnostics = Vec::new();
+            if let Some(start) = text.find("ab") {
+                let fix = Fix::edit(replace(start, start + 2, "ABC"));
+                diagnostics.push(diagnostic(Rule::MixedScripts, fix));
+            }
+            if !text.ends_with('!') {
+                let fix = Fix::edit(Edit::insertion("!".to_string(), text.len()));
+                diagnostics.push(diagnostic(Rule::Punctuation, fix));
+            }
+            diagnostics
+        };
+        let config = [Rule::MixedScripts, Rule::Punctuation];
+        let report = fix_with("ab", &config, check);
+        assert_eq!(report.text, "ABC!");
+        assert_eq!(report.passes, 2);
+        assert!(report.remaining.is_empty());
+        assert_eq!(report.error, None);
+        // The insertion is at the end of the original text.
+        assert_eq!(
+            report.applied,
+            [
+                AppliedFix {
+                    rule: Rule::MixedScripts,
+                    range: TextRange::new(0, 2),
+                    pass: 1,
+                },
+                AppliedFix {
+                    rule: Rule::Punctuation,
+                    range: TextRange::new(2, 2),
+                    pass: 2,
+                },
+            ]
+        );
+        assert_eq!(report.counter()[&Rule::Punctuation], 1);
+    }
+
+    #[test]
+    fn test_remaining() {
+        // FC has no preferred fix.
+        let report = fix(
+            "Πιςτεύω στην δόξα",
+            &[Rule::ForbiddenChar, Rule::RemoveFinalN],
+        );
+        assert_eq!(report.text, "Πιςτεύω στη δόξα");
+        let remaining = report
+            .remaining
+            .iter()
+            .map(|diagnostic| diagnostic.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, [Rule::ForbiddenChar]);
+    }
+
+    #[test]
+    fn test_map_offset() {
+        // "αβγ" -> "αXYγ" -> "Zαγ"
+        let history = [
+            vec![replace(2, 4, "XY")],
+            vec![Edit::insertion("Z".to_string(), 0), replace(2, 4, "")],
+        ];
+        assert_eq!(
+            map_range(&history, TextRange::new(0, 1)),
+            TextRange::new(0, 0)
+        );
+        assert_eq!(
+            map_range(&history, TextRange::new(1, 3)),
+            TextRange::new(0, 2)
+        );
+        assert_eq!(
+            map_range(&history, TextRange::new(3, 3)),
+            TextRange::new(4, 4)
+        );
+        assert_eq!(
+            map_range(&history, TextRange::new(3, 5)),
+            TextRange::new(4, 6)
+        );
+    }
+
+    #[test]
+    fn test_original_ranges() {
+        let text = "Aλλά στην δόξα";
+        let report = fix(text, &[Rule::MixedScripts, Rule::RemoveFinalN]);
+        assert_eq!(report.text, "Αλλά στη δόξα");
+        let ranges = report.ranges(Rule::RemoveFinalN).collect::<Vec<_>>();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(&text[ranges[0].start()..ranges[0].end()], "ν");
+    }
+
+    #[test]
+    fn test_cycle() {
+        // Two rules undoing each other.
+        let check = |text: &str, _: &RuleSet| {
+            let (rule, content) = if text == "α" {
+                (Rule::MixedScripts, "a")
+            } else {
+                (Rule::AmbiguousChar, "α")
+            };
+            let fix = Fix::edit(replace(0, text.len(), content));
+            vec![diagnostic(rule, fix)]
+        };
+        let config = [Rule::MixedScripts, Rule::AmbiguousChar];
+        let report = fix_with("α", &config, check);
+        assert_eq!(report.passes, 2);
+        assert_eq!(report.text, "α");
+        assert_eq!(report.remaining.len(), 1);
+        assert_eq!(
+            report.error,
+            Some(FixError::Cycle {
+                rules: vec![Rule::MixedScripts, Rule::AmbiguousChar],
+                pass: 0,
+            })
+        );
+    }
+}
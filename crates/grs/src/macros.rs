@@ -37,8 +37,7 @@ macro_rules! test_fix {
         #[test]
         fn $name() {
             let text = $text;
-            let res = $crate::linter::fix(text, $config);
-            let received = res.0;
+            let received = $crate::linter::fix(text, $config).text;
             assert_eq!(received, $expected, "(text: {text})");
         }
     };
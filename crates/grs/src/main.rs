@@ -1,19 +1,35 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 use clap::CommandFactory;
 use clap::Parser;
 use clap_complete::generate;
 use colored::Colorize;
+use encoding_rs::Encoding;
 use itertools::Itertools;
 
 use grs::cli::{Args, CheckCommand, Command};
-use grs::linter::{fix, lint_only};
+use grs::diagnostic::{ReportOptions, Severity};
+use grs::encoding::{self, FileEncoding};
+use grs::formats;
+use grs::formats::FormatOptions;
+use grs::formats::mediawiki;
+use grs::formats::wikitext::Wikitext;
+use grs::linter::{fix, fix_filtered, lint_only_filtered};
 use grs::registry::Rule;
+use grs::settings::Settings;
 use grs::text_diff::CodeDiff;
 
+mod files;
+mod git;
+mod ispell;
+mod languagetool;
+mod pandoc;
+mod server;
+
 #[derive(Copy, Clone)]
 pub enum ExitStatus {
     Success,
@@ -29,24 +45,77 @@ impl From<ExitStatus> for ExitCode {
     }
 }
 
-fn read_file(path: &PathBuf) -> Result<String, ExitStatus> {
-    std::fs::read_to_string(path).map_err(|err| {
-        eprintln!("Failed to read file {}: {err}", path.display());
-        ExitStatus::Failure
-    })
+fn read_file(
+    path: &PathBuf,
+    encoding: Option<&'static Encoding>,
+) -> Result<(String, FileEncoding), ExitStatus> {
+    std::fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| encoding::decode(&bytes, encoding))
+        .map_err(|err| {
+            eprintln!("Failed to read file {}: {err}", path.display());
+            ExitStatus::Failure
+        })
 }
 
-fn write_file(path: &PathBuf, content: &str) -> Result<(), ExitStatus> {
-    std::fs::write(path, content).map_err(|err| {
-        eprintln!("Failed to write to file {}: {err}", path.display());
-        ExitStatus::Failure
-    })
+/// Read the staged content of a file, for `check --staged`.
+fn read_staged(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+) -> Result<(String, FileEncoding), ExitStatus> {
+    git::staged_content(path)
+        .and_then(|bytes| encoding::decode(&bytes, encoding))
+        .map_err(|err| {
+            eprintln!("Failed to read the staged file {}: {err}", path.display());
+            ExitStatus::Failure
+        })
+}
+
+/// Replace the `original` content of a file with `content`, if different, in
+/// the encoding and with the line endings of the original.
+fn write_file(
+    path: &Path,
+    original: &str,
+    content: &str,
+    encoding: FileEncoding,
+    backup: Option<&str>,
+) -> Result<(), ExitStatus> {
+    if content == original {
+        return Ok(());
+    }
+    let content = files::with_line_endings(original, content);
+    write_encoded(path, &content, encoding, backup)
+}
+
+fn write_encoded(
+    path: &Path,
+    content: &str,
+    encoding: FileEncoding,
+    backup: Option<&str>,
+) -> Result<(), ExitStatus> {
+    let backup = backup.map(|suffix| files::backup_path(path, suffix));
+    encoding::encode(content, encoding)
+        .and_then(|bytes| {
+            files::write_atomic(path, &bytes, backup.as_deref()).map_err(|err| err.to_string())
+        })
+        .map_err(|err| {
+            eprintln!("Failed to write to file {}: {err}", path.display());
+            ExitStatus::Failure
+        })
+}
+
+fn parse_error(path: &Path, err: &str) -> ExitStatus {
+    eprintln!("Failed to parse file {}: {err}", path.display());
+    ExitStatus::Failure
 }
 
-fn get_text_files(files: Vec<PathBuf>) -> Result<Vec<PathBuf>, ExitStatus> {
+fn get_text_files(
+    files: Vec<PathBuf>,
+    supported: impl Fn(&Path) -> bool,
+) -> Result<Vec<PathBuf>, ExitStatus> {
     let text_files = files
         .into_iter()
-        .filter(|file| file.extension().and_then(|ext| ext.to_str()) == Some("txt"))
+        .filter(|file| supported(file))
         .collect::<Vec<_>>();
     if text_files.is_empty() {
         Err(ExitStatus::Success)
@@ -66,10 +135,23 @@ fn run() -> Result<ExitStatus, ExitStatus> {
     let args = Args::parse();
 
     match args.command {
-        Command::Check(check_args) => time_it("Execution time", || run_check_command(check_args)),
+        Command::Check(check_args) => time_it("Execution time", || run_check_command(*check_args)),
         Command::ToMonotonic { files } => {
             time_it("Execution time", || run_to_monotonic_command(files))
         }
+        Command::ConvertEncoding {
+            from,
+            to,
+            files: paths,
+        } => run_convert_encoding_command(from, to, &paths),
+        // Nothing must be printed to stdout, which is used by the protocol.
+        Command::Server => server::run().map(|()| ExitStatus::Success).map_err(|err| {
+            eprintln!("Language server failed: {err}");
+            ExitStatus::Failure
+        }),
+        Command::Serve { port, host } => run_serve_command(&host, port),
+        Command::Pipe { .. } => run_pipe_command(),
+        Command::PandocFilter { fix, format: _ } => run_pandoc_filter_command(fix),
         Command::GenerateCompletions { shell } => {
             // https://github.com/BurntSushi/ripgrep/blob/master/FAQ.md#complete
             // grs generate-completions fish > ~/.config/fish/completions/grs.fish
@@ -81,42 +163,192 @@ fn run() -> Result<ExitStatus, ExitStatus> {
     }
 }
 
+/// Settings of the project in the current directory.
+fn project_settings() -> Result<Settings, ExitStatus> {
+    std::env::current_dir()
+        .map_err(|err| err.to_string())
+        .and_then(|dir| Settings::discover(&dir))
+        .map_err(|err| {
+            eprintln!("Failed to read configuration: {err}");
+            ExitStatus::Failure
+        })
+}
+
+/// Show the warnings of the settings in use.
+fn print_warnings(settings: &Settings) {
+    for warning in settings.warnings() {
+        eprintln!("Warning: {warning}");
+    }
+}
+
+fn run_serve_command(host: &str, port: u16) -> Result<ExitStatus, ExitStatus> {
+    let settings = project_settings()?;
+    print_warnings(&settings);
+    languagetool::run(host, port, &settings.rules(), &settings.report_options()).map_err(
+        |err| {
+            eprintln!("Failed to start the server: {err}");
+            ExitStatus::Failure
+        },
+    )?;
+    Ok(ExitStatus::Success)
+}
+
+fn run_pipe_command() -> Result<ExitStatus, ExitStatus> {
+    let settings = project_settings()?;
+    print_warnings(&settings);
+    let rules = settings.rules();
+    ispell::run(&rules).map_err(|err| {
+        eprintln!("{err}");
+        ExitStatus::Failure
+    })?;
+    Ok(ExitStatus::Success)
+}
+
+fn run_pandoc_filter_command(fix: bool) -> Result<ExitStatus, ExitStatus> {
+    let settings = project_settings()?;
+    print_warnings(&settings);
+    pandoc::run(&settings.rules(), &settings.report_options(), fix).map_err(|err| {
+        eprintln!("Failed to filter the document: {err}");
+        ExitStatus::Failure
+    })?;
+    Ok(ExitStatus::Success)
+}
+
 fn run_to_monotonic_command(files: Vec<PathBuf>) -> Result<ExitStatus, ExitStatus> {
-    let text_files = get_text_files(files)?;
+    // The conversion runs over the whole file, so markup would be converted too.
+    let text_files = get_text_files(files, |file| {
+        file.extension().and_then(|ext| ext.to_str()) == Some("txt")
+    })?;
     for file in &text_files {
-        let text = read_file(file)?;
+        let (text, encoding) = read_file(file, None)?;
         let monotonic = grac::to_monotonic(&text);
-        write_file(file, &monotonic)?;
+        write_file(file, &text, &monotonic, encoding, None)?;
     }
     println!("Successfully converted to monotonic.");
     Ok(ExitStatus::Success)
 }
 
-fn run_check_command(args: CheckCommand) -> Result<ExitStatus, ExitStatus> {
-    let text_files = get_text_files(args.files)?;
+fn run_convert_encoding_command(
+    from: Option<&'static Encoding>,
+    to: &'static Encoding,
+    paths: &[PathBuf],
+) -> Result<ExitStatus, ExitStatus> {
+    for file in paths {
+        let (text, original) = read_file(file, from)?;
+        // Only UTF-16 needs a byte order mark to be detected.
+        let bom = if to == original.encoding {
+            original.bom
+        } else {
+            to == encoding_rs::UTF_16LE || to == encoding_rs::UTF_16BE
+        };
+        write_encoded(file, &text, FileEncoding { encoding: to, bom }, None)?;
+        println!("{}: {} -> {}", file.display(), original.name(), to.name());
+    }
+    Ok(ExitStatus::Success)
+}
 
-    let default_rules: Vec<_> = ["MDA", "OS", "MA", "MNA"]
-        .iter()
-        .map(|code| code.parse::<Rule>().unwrap())
-        .collect();
+/// Number of pages shown in the summary of a dump.
+const DUMP_SUMMARY_SIZE: usize = 10;
 
-    let mut config: Vec<Rule> = args.select.map_or(default_rules, |selection| {
-        selection
-            .iter()
-            .flat_map(grs::cli::RuleSelector::rules)
-            .unique()
-            .collect()
-    });
-
-    // Does not crash if rules to ignore were not in config.
-    if let Some(selection) = args.ignore {
-        config.retain(|rule| {
-            !selection
-                .iter()
-                .any(|selector| selector.rules().contains(rule))
-        });
+fn check_mediawiki_dump(
+    path: &Path,
+    config: &[Rule],
+    report: &ReportOptions,
+    statistics: bool,
+    global_statistics_counter: &mut HashMap<Rule, usize>,
+) -> Result<(), ExitStatus> {
+    let pages = mediawiki::open(path).map_err(|err| {
+        eprintln!("Failed to read dump {}: {err}", path.display());
+        ExitStatus::Failure
+    })?;
+
+    // The pages with the most errors, the fewest on top so that they are
+    // dropped first, and among equal counts the latest page in the dump.
+    let mut most_errors = BinaryHeap::with_capacity(DUMP_SUMMARY_SIZE + 1);
+    for (index, page) in pages.enumerate() {
+        let page = page.map_err(|err| parse_error(path, &err))?;
+        // Only articles
+        if page.ns != 0 || page.redirect {
+            continue;
+        }
+
+        let (messages, statistics_counter) =
+            formats::lint_only(&Wikitext, &page.text, config, report)
+                .map_err(|err| parse_error(path, &err))?;
+        if messages.is_empty() {
+            continue;
+        }
+
+        if !statistics {
+            let header = format!("{} (revision {})", page.title, page.revision);
+            println!("{}\n{}", header.purple(), messages.join("\n"));
+        }
+        for (key, value) in statistics_counter {
+            *global_statistics_counter.entry(key).or_insert(0) += value;
+        }
+        let entry = (messages.len(), Reverse(index), page.title, page.revision);
+        most_errors.push(Reverse(entry));
+        if most_errors.len() > DUMP_SUMMARY_SIZE {
+            most_errors.pop();
+        }
+    }
+
+    if !most_errors.is_empty() {
+        println!("Pages with the most errors:");
+        let errors_per_page = most_errors.into_sorted_vec();
+        let padding = errors_per_page[0].0.0.to_string().len();
+        for Reverse((n_errors, _, title, revision)) in &errors_per_page {
+            println!("{n_errors:padding$}    {title} (revision {revision})");
+        }
     }
 
+    Ok(())
+}
+
+fn run_check_command(args: CheckCommand) -> Result<ExitStatus, ExitStatus> {
+    let format_options = FormatOptions {
+        include_fuzzy: args.include_fuzzy,
+        keys: args.keys,
+        csv: args.csv,
+        columns: args.columns,
+    };
+
+    let changed = if args.staged || args.diff_base.is_some() {
+        let changed = git::changed_lines(args.diff_base.as_deref()).map_err(|err| {
+            eprintln!("Failed to get the changes from git: {err}");
+            ExitStatus::Failure
+        })?;
+        Some(changed)
+    } else {
+        None
+    };
+    let files = match &changed {
+        Some(changed) if args.files.is_empty() => changed.keys().sorted().cloned().collect(),
+        _ => args.files,
+    };
+
+    let text_files = if args.mediawiki_dump.is_some() {
+        Vec::new()
+    } else {
+        get_text_files(files, |file| formats::is_supported(file, &format_options))?
+    };
+
+    // The command line takes precedence over the project configuration.
+    let mut settings = Settings {
+        select: args.select,
+        ignore: args.ignore,
+        preview: args.preview.then_some(true),
+        severity: Vec::new(),
+        lang: args.lang,
+    };
+    settings.set_severity(args.info, Severity::Info);
+    settings.set_severity(args.warn, Severity::Warning);
+    settings.set_severity(args.error, Severity::Error);
+    let settings = settings.or(project_settings()?);
+    print_warnings(&settings);
+    let config = settings.rules();
+    let report = settings.report_options();
+
     println!(
         "Config: [{}]",
         config
@@ -127,22 +359,83 @@ fn run_check_command(args: CheckCommand) -> Result<ExitStatus, ExitStatus> {
     );
 
     let mut global_statistics_counter = HashMap::new();
+    // The diagnostics left after fixing, per rule.
+    let mut remaining_counter: HashMap<Rule, usize> = HashMap::new();
+
+    if let Some(dump) = &args.mediawiki_dump {
+        check_mediawiki_dump(
+            dump,
+            &config,
+            &report,
+            args.statistics,
+            &mut global_statistics_counter,
+        )?;
+    }
 
     for file in &text_files {
-        let text = read_file(file)?;
+        // Only the changed lines of changed files, if asked to.
+        let lines = match &changed {
+            Some(changed) => {
+                let path = file.canonicalize().unwrap_or_else(|_| file.clone());
+                let Some(lines) = changed.get(&path) else {
+                    continue;
+                };
+                Some(lines)
+            }
+            None => None,
+        };
+        let keep = |text: &str, range| lines.is_none_or(|lines| lines.intersects(text, range));
+
+        let (text, encoding) = if args.staged {
+            read_staged(file, args.encoding)?
+        } else {
+            read_file(file, args.encoding)?
+        };
+        let format = formats::from_path(file, &format_options);
+        let fix_text = |text: &str| {
+            let fixed = match (&format, lines) {
+                (Some(format), None) => formats::fix(format.as_ref(), text, &config)
+                    .map_err(|err| parse_error(file, &err))?,
+                (Some(format), Some(_)) => {
+                    formats::fix_filtered(format.as_ref(), text, &config, keep)
+                        .map_err(|err| parse_error(file, &err))?
+                }
+                (None, None) => fix(text, &config),
+                (None, Some(_)) => fix_filtered(text, &config, keep),
+            };
+            // The text is still written: every pass only applies valid fixes.
+            if let Some(err) = &fixed.error {
+                eprintln!("Warning: {}: {err}", file.display());
+            }
+            Ok(fixed)
+        };
 
         let statistics_counter = if args.diff {
-            let (fixed, _messages, statistics_counter) = fix(&text, &config);
+            let fixed = fix_text(&text)?;
             // I dont know how to remove colors
-            let text_diff = CodeDiff::new(&text, &fixed);
+            let text_diff = CodeDiff::new(&text, &fixed.text);
             println!("{text_diff}");
-            statistics_counter
+            for diagnostic in &fixed.remaining {
+                *remaining_counter.entry(diagnostic.kind).or_insert(0) += 1;
+            }
+            fixed.counter()
         } else if args.fix {
-            let (fixed, _messages, statistics_counter) = fix(&text, &config);
-            write_file(file, &fixed)?;
-            statistics_counter
+            let fixed = fix_text(&text)?;
+            write_file(file, &text, &fixed.text, encoding, args.backup.as_deref())?;
+            for diagnostic in &fixed.remaining {
+                *remaining_counter.entry(diagnostic.kind).or_insert(0) += 1;
+            }
+            fixed.counter()
         } else {
-            let (messages, statistics_counter) = lint_only(&text, &config);
+            let (messages, statistics_counter) = match &format {
+                Some(format) => {
+                    formats::lint_only_filtered(format.as_ref(), &text, &config, &report, |range| {
+                        keep(&text, range)
+                    })
+                    .map_err(|err| parse_error(file, &err))?
+                }
+                None => lint_only_filtered(&text, &config, &report, |range| keep(&text, range)),
+            };
             if !args.statistics && !messages.is_empty() {
                 // Header
                 // println!("{}", file.to_str().unwrap().purple());
@@ -168,10 +461,11 @@ fn run_check_command(args: CheckCommand) -> Result<ExitStatus, ExitStatus> {
             .sorted_by(|a, b| b.1.cmp(a.1))
             .for_each(|(k, v)| {
                 println!(
-                    "{:padding$}    {:<4}   [{}] {:?}",
+                    "{:padding$}    {:<4}   [{}] {:<7} {:?}",
                     v,
                     format!("{k}").red().bold(),
                     (if k.has_fix() { "*" } else { " " }).to_string().cyan(),
+                    report.severities.get(*k).name(),
                     k
                 );
             });
@@ -182,20 +476,51 @@ fn run_check_command(args: CheckCommand) -> Result<ExitStatus, ExitStatus> {
         .iter()
         .filter_map(|(rule, cnt)| if rule.has_fix() { Some(cnt) } else { None })
         .sum::<usize>();
+    let n_with_severity = |counter: &HashMap<Rule, usize>, severity: Severity| {
+        counter
+            .iter()
+            .filter(|(rule, _)| report.severities.get(**rule) == severity)
+            .map(|(_, cnt)| cnt)
+            .sum::<usize>()
+    };
 
-    if n_errors == 0 {
+    let n_remaining = remaining_counter.values().sum::<usize>();
+    if n_errors == 0 && n_remaining == 0 {
         println!("No errors!");
-    } else if args.fix {
-        println!("Fixed {n_errors} errors.");
+    } else if args.fix || args.diff {
+        if args.fix {
+            println!("Fixed {n_errors} errors.");
+        }
+        if n_remaining > 0 {
+            println!(
+                "{} errors remain ({} error, {} warning, {} info).",
+                n_remaining,
+                n_with_severity(&remaining_counter, Severity::Error),
+                n_with_severity(&remaining_counter, Severity::Warning),
+                n_with_severity(&remaining_counter, Severity::Info),
+            );
+        }
     } else {
         println!(
-            "Found {} errors.\n[{}] {} fixable with the `--fix` option.",
+            "Found {} errors ({} error, {} warning, {} info).\n[{}] {} fixable with the `--fix` option.",
             n_errors,
+            n_with_severity(&global_statistics_counter, Severity::Error),
+            n_with_severity(&global_statistics_counter, Severity::Warning),
+            n_with_severity(&global_statistics_counter, Severity::Info),
             "*".to_string().cyan(),
             n_fixable_errors,
         );
     }
 
+    // Only diagnostics of error severity, left unfixed, make the check fail.
+    let unfixed = if args.fix || args.diff {
+        &remaining_counter
+    } else {
+        &global_statistics_counter
+    };
+    if n_with_severity(unfixed, Severity::Error) > 0 {
+        return Ok(ExitStatus::Failure);
+    }
     Ok(ExitStatus::Success)
 }
 
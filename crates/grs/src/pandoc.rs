@@ -0,0 +1,294 @@
+//! Pandoc JSON filter (`grs pandoc-filter`).
+//!
+//! The `Str`, `Space` and break inlines of every block are joined back into
+//! prose and linted. Diagnostics go to stderr, labeled with the JSON pointer of
+//! their block, and the AST goes to stdout, fixed if asked to.
+//!
+//! Only fixes inside a single `Str` are applied: an inline can not be split or
+//! merged without guessing the intent of the author.
+//!
+//! <https://pandoc.org/filters.html>
+use std::borrow::Cow;
+use std::io::Read;
+
+use serde_json::Value;
+
+use grs::diagnostic::ReportOptions;
+use grs::formats::{self, Format, Segment, SegmentBuilder};
+use grs::linter::{self, Counter};
+use grs::range::TextRange;
+use grs::registry::Rule;
+
+/// Characters standing for the inlines that are not `Str` in the flattened
+/// source of a block. They can not appear in a `Str`.
+const END_OF_STR: char = '\u{0}';
+const SPACE: char = '\u{1}';
+const BREAK: char = '\u{2}';
+const OPAQUE: char = '\u{3}';
+const MARKERS: [char; 4] = [END_OF_STR, SPACE, BREAK, OPAQUE];
+
+/// The flattened source of the inlines of a block: the content of every `Str`
+/// followed by [`END_OF_STR`], and a marker for any other inline.
+struct Inlines;
+
+impl Format for Inlines {
+    fn segments(&self, source: &str) -> Result<Vec<Segment>, String> {
+        let mut builder = SegmentBuilder::new();
+        let mut start = 0;
+        for (idx, marker) in source.match_indices(MARKERS) {
+            builder.verbatim(&source[start..idx], start);
+            let range = TextRange::new(idx, idx + marker.len());
+            match marker.chars().next() {
+                Some(SPACE) => builder.synthetic(" ", range),
+                Some(BREAK) => builder.synthetic("\n", range),
+                Some(OPAQUE) => builder.opaque(range),
+                _ => builder.synthetic("", range),
+            }
+            start = idx + marker.len();
+        }
+        builder.verbatim(&source[start..], start);
+        Ok(vec![builder.finish(None)])
+    }
+
+    fn escape<'a>(&self, replacement: &'a str) -> Option<Cow<'a, str>> {
+        (!replacement.contains(|c: char| c.is_whitespace() || MARKERS.contains(&c)))
+            .then_some(Cow::Borrowed(replacement))
+    }
+}
+
+/// The inlines of a block.
+struct Block {
+    /// JSON pointer of the block.
+    pointer: String,
+    kind: String,
+    /// JSON pointers of the `Str` inlines, in order.
+    strs: Vec<String>,
+    source: String,
+}
+
+impl Block {
+    fn new(
+        pointer: &str,
+        kind: &str,
+        inlines: &Value,
+        inlines_pointer: &str,
+        notes: &mut Vec<(Value, String)>,
+    ) -> Self {
+        let mut block = Self {
+            pointer: pointer.to_string(),
+            kind: kind.to_string(),
+            strs: Vec::new(),
+            source: String::new(),
+        };
+        block.push_inlines(inlines, inlines_pointer, notes);
+        block
+    }
+
+    fn push_inlines(&mut self, inlines: &Value, pointer: &str, notes: &mut Vec<(Value, String)>) {
+        let Some(inlines) = inlines.as_array() else {
+            return;
+        };
+        for (idx, inline) in inlines.iter().enumerate() {
+            let pointer = format!("{pointer}/{idx}");
+            let content = &inline["c"];
+            match inline["t"].as_str().unwrap_or_default() {
+                "Str" => {
+                    let text = content.as_str().unwrap_or_default();
+                    // Markers would be taken for inlines.
+                    self.source.push_str(&text.replace(MARKERS, ""));
+                    self.source.push(END_OF_STR);
+                    self.strs.push(format!("{pointer}/c"));
+                }
+                "Space" => self.source.push(SPACE),
+                "SoftBreak" | "LineBreak" => self.source.push(BREAK),
+                "Emph" | "Underline" | "Strong" | "Strikeout" | "Superscript" | "Subscript"
+                | "SmallCaps" => self.push_inlines(content, &format!("{pointer}/c"), notes),
+                "Quoted" | "Cite" | "Link" | "Image" | "Span" => {
+                    self.push_inlines(&content[1], &format!("{pointer}/c/1"), notes);
+                }
+                "Note" => {
+                    self.source.push(OPAQUE);
+                    notes.push((content.clone(), format!("{pointer}/c")));
+                }
+                // Code, math and raw inlines.
+                _ => self.source.push(OPAQUE),
+            }
+        }
+    }
+}
+
+/// Collect the blocks with inlines of `value`, found at `pointer`.
+fn collect(value: &Value, pointer: &str, blocks: &mut Vec<Block>) {
+    let mut notes = Vec::new();
+    let mut block = |kind: &str, inlines: &Value, inlines_pointer: String| {
+        Block::new(pointer, kind, inlines, &inlines_pointer, &mut notes)
+    };
+
+    match value {
+        Value::Object(object) if object.contains_key("t") => {
+            let kind = object["t"].as_str().unwrap_or_default();
+            let content = &value["c"];
+            match kind {
+                "Para" | "Plain" | "MetaInlines" => {
+                    blocks.push(block(kind, content, format!("{pointer}/c")));
+                }
+                "Header" => blocks.push(block(kind, &content[2], format!("{pointer}/c/2"))),
+                "LineBlock" => {
+                    for (idx, line) in content.as_array().into_iter().flatten().enumerate() {
+                        blocks.push(block(kind, line, format!("{pointer}/c/{idx}")));
+                    }
+                }
+                "DefinitionList" => {
+                    for (idx, item) in content.as_array().into_iter().flatten().enumerate() {
+                        blocks.push(block(kind, &item[0], format!("{pointer}/c/{idx}/0")));
+                        collect(&item[1], &format!("{pointer}/c/{idx}/1"), blocks);
+                    }
+                }
+                "CodeBlock" | "RawBlock" | "MetaString" => {}
+                _ => collect(content, &format!("{pointer}/c"), blocks),
+            }
+        }
+        Value::Object(object) => {
+            for (key, value) in object {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                collect(value, &format!("{pointer}/{key}"), blocks);
+            }
+        }
+        Value::Array(values) => {
+            for (idx, value) in values.iter().enumerate() {
+                collect(value, &format!("{pointer}/{idx}"), blocks);
+            }
+        }
+        _ => {}
+    }
+
+    for (note, pointer) in notes {
+        collect(&note, &pointer, blocks);
+    }
+}
+
+/// Lint the document `ast`, reporting to stderr, and fix it if asked to.
+fn filter(
+    ast: &mut Value,
+    rules: &[Rule],
+    report: &ReportOptions,
+    fix: bool,
+) -> Result<Counter, String> {
+    let mut blocks = Vec::new();
+    collect(ast, "", &mut blocks);
+
+    let mut statistics = Counter::new();
+    for block in blocks {
+        let prose = Inlines.segments(&block.source)?.remove(0).text;
+        let (messages, counter) = linter::lint_only(&prose, rules, report);
+        for message in messages {
+            eprintln!("{} ({}): {message}", block.pointer, block.kind);
+        }
+        for (rule, count) in counter {
+            *statistics.entry(rule).or_insert(0) += count;
+        }
+
+        if fix {
+            let fixed = formats::fix(&Inlines, &block.source, rules)?.text;
+            let texts = fixed
+                .split(END_OF_STR)
+                .map(|text| text.replace(MARKERS, ""));
+            for (pointer, text) in block.strs.iter().zip(texts) {
+                if let Some(value) = ast.pointer_mut(pointer) {
+                    *value = Value::String(text);
+                }
+            }
+        }
+    }
+    Ok(statistics)
+}
+
+/// Filter the AST read from stdin to stdout.
+///
+/// # Errors
+///
+/// Returns an error message if the AST can not be read.
+pub fn run(rules: &[Rule], report: &ReportOptions, fix: bool) -> Result<(), String> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|err| err.to_string())?;
+    let mut ast: Value = serde_json::from_str(&input).map_err(|err| err.to_string())?;
+    let statistics = filter(&mut ast, rules, report, fix)?;
+    let n_errors = statistics.values().sum::<usize>();
+    if n_errors > 0 {
+        eprintln!("Found {n_errors} errors.");
+    }
+    println!("{ast}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pandoc -t json <<< '# Τίτλος
+
+    // Να μην *την* δώσεις `κώδικα`.[^1]
+
+    // [^1]: Σε κανενανε!'
+    const AST: &str = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+        {"t":"Header","c":[1,["titlos",[],[]],[{"t":"Str","c":"Τίτλος"}]]},
+        {"t":"Para","c":[{"t":"Str","c":"Να"},{"t":"Space"},{"t":"Str","c":"μην"},{"t":"Space"},
+            {"t":"Emph","c":[{"t":"Str","c":"την"}]},{"t":"Space"},{"t":"Str","c":"δώσεις"},
+            {"t":"Space"},{"t":"Code","c":[["",[],[]],"κώδικα"]},{"t":"Str","c":"."},
+            {"t":"Note","c":[{"t":"Para","c":[{"t":"Str","c":"Σε"},{"t":"Space"},
+                {"t":"Str","c":"κανενανε!"}]}]}]}]}"#;
+
+    #[test]
+    fn test_collect() {
+        let ast: Value = serde_json::from_str(AST).unwrap();
+        let mut blocks = Vec::new();
+        collect(&ast, "", &mut blocks);
+        let blocks: Vec<_> = blocks
+            .iter()
+            .map(|block| {
+                let prose = Inlines.segments(&block.source).unwrap().remove(0).text;
+                (block.pointer.as_str(), prose)
+            })
+            .collect();
+        assert_eq!(
+            blocks,
+            [
+                ("/blocks/0", "Τίτλος".to_string()),
+                ("/blocks/1", "Να μην την δώσεις 0.0".to_string()),
+                ("/blocks/1/c/10/c/0", "Σε κανενανε!".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix() {
+        let mut ast: Value = serde_json::from_str(AST).unwrap();
+        let statistics = filter(
+            &mut ast,
+            &[Rule::RemoveFinalN],
+            &ReportOptions::default(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(statistics[&Rule::RemoveFinalN], 1);
+        assert_eq!(ast["blocks"][1]["c"][4]["c"][0]["c"], "τη");
+        assert_eq!(ast["blocks"][1]["c"][2]["c"], "μην");
+    }
+
+    #[test]
+    fn test_fix_across_inlines() {
+        // A fix can not span several inlines.
+        let source = "α\u{0}\u{1}β\u{0}";
+        let segment = Inlines.segments(source).unwrap().remove(0);
+        assert_eq!(segment.text, "α β");
+        assert!(
+            segment
+                .map
+                .to_source(TextRange::new(0, segment.text.len()))
+                .is_none()
+        );
+        assert!(Inlines.escape("α β").is_none());
+    }
+}
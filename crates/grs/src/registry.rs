@@ -1,74 +1,278 @@
-use strum::IntoEnumIterator;
-use strum_macros::{EnumIter, IntoStaticStr};
+//! Rules and their metadata.
+//!
+//! Every rule implements [`LintRule`], declaring its metadata and the checks
+//! of its [`Phase`]. A [`Rule`] is a cheap handle to one: the built-in rules
+//! are associated constants, ex. [`Rule::RemoveFinalN`], and other crates can
+//! make their own with [`Rule::new`] and add them to a config.
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::LazyLock;
+
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::rules;
+use crate::tokenizer::{Doc, Token};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-#[derive(EnumIter, IntoStaticStr, Clone, Copy, PartialEq, Eq, Hash)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub enum Rule {
-    MissingDoubleAccents,
-    MissingAccentCapital,
-    DuplicatedWord,
-    AddFinalN,
-    RemoveFinalN,
-    OutdatedSpelling,
-    MonosyllableAccented,
-    MultisyllableNotAccented,
-    MixedScripts,
-    AmbiguousChar,
-    ForbiddenAccent,
-    ForbiddenDoubleAccent,
-    ForbiddenChar,
-    Punctuation,
+/// What a rule looks at, which decides when it is called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// The raw text, without tokenizing.
+    Raw,
+    /// Every Greek word, with the document for context.
+    Token,
+    /// The tokens of every sentence.
+    Sentence,
+    /// The whole tokenized document.
+    Document,
+}
+
+/// Category of a rule, to select related rules at once, ex. `--select accent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Group {
+    /// Missing, superfluous or misplaced accents.
+    Accent,
+    /// Characters that do not belong in Greek text.
+    Char,
+    Grammar,
+    Typography,
+}
+
+impl Group {
+    pub const ALL: [Self; 4] = [Self::Accent, Self::Char, Self::Grammar, Self::Typography];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Accent => "accent",
+            Self::Char => "char",
+            Self::Grammar => "grammar",
+            Self::Typography => "typography",
+        }
+    }
+
+    /// The built-in rules of the group.
+    pub fn rules(self) -> impl Iterator<Item = Rule> {
+        Rule::iter().filter(move |rule| rule.group() == self)
+    }
+}
+
+impl std::str::FromStr for Group {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|group| group.name() == name)
+            .ok_or_else(|| format!("Unknown rule group: {name}"))
+    }
 }
 
+impl std::fmt::Display for Group {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Metadata of a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RuleMeta {
+    /// Short code, as used in configs and on the command line, ex. `RFN`.
+    pub code: &'static str,
+    /// Name, ex. `RemoveFinalN`.
+    pub name: &'static str,
+    pub phase: Phase,
+    pub group: Group,
+    /// Whether its diagnostics come with a fix.
+    pub fixable: bool,
+    /// Whether it is checked when no rule is selected.
+    pub default: bool,
+    /// Whether it is experimental, and only checked in preview mode.
+    pub preview: bool,
+    /// Severity of its diagnostics, unless overridden by the config.
+    pub severity: Severity,
+    /// One line description.
+    pub docs: &'static str,
+}
+
+/// A rule: its metadata and its checks.
+///
+/// Only the check of the phase of the rule is called. The others default to
+/// doing nothing.
+pub trait LintRule: Sync {
+    fn meta(&self) -> &'static RuleMeta;
+
+    fn check_raw(&self, _text: &str, _diagnostics: &mut Vec<Diagnostic>) {}
+
+    fn check_token(&self, _token: &Token, _doc: &Doc, _diagnostics: &mut Vec<Diagnostic>) {}
+
+    fn check_sentence(&self, _sentence: &[Token], _doc: &Doc, _diagnostics: &mut Vec<Diagnostic>) {}
+
+    fn check_document(&self, _doc: &Doc, _diagnostics: &mut Vec<Diagnostic>) {}
+}
+
+/// Handle to a rule. Two rules are equal if they have the same code.
+#[derive(Clone, Copy)]
+pub struct Rule(&'static dyn LintRule);
+
+#[allow(non_upper_case_globals)]
 impl Rule {
-    // Having hardcoded this here over extracting it from the rule
-    // implementations is not ideal.
-    pub const fn has_fix(&self) -> bool {
-        #[allow(clippy::enum_glob_use)]
-        use Rule::*;
-        matches!(
-            self,
-            MissingDoubleAccents
-                | MissingAccentCapital
-                | AddFinalN
-                | RemoveFinalN
-                | OutdatedSpelling
-                | MonosyllableAccented
-                | MixedScripts
-                | AmbiguousChar
-                | ForbiddenDoubleAccent
-                | Punctuation
-        )
-    }
-
-    pub const fn requires_tokenizing(&self) -> bool {
-        !matches!(
-            self,
-            Self::OutdatedSpelling | Self::AmbiguousChar | Self::ForbiddenChar
-        )
-    }
-}
-
-fn name_to_code(name: &str) -> String {
-    name.chars().filter(|c| c.is_uppercase()).collect()
+    pub const MissingDoubleAccents: Self = Self(&rules::MissingDoubleAccents);
+    pub const MissingAccentCapital: Self = Self(&rules::MissingAccentCapital);
+    pub const DuplicatedWord: Self = Self(&rules::DuplicatedWord);
+    pub const AddFinalN: Self = Self(&rules::AddFinalN);
+    pub const RemoveFinalN: Self = Self(&rules::RemoveFinalN);
+    pub const OutdatedSpelling: Self = Self(&rules::OutdatedSpelling);
+    pub const MonosyllableAccented: Self = Self(&rules::MonosyllableAccented);
+    pub const MultisyllableNotAccented: Self = Self(&rules::MultisyllableNotAccented);
+    pub const MixedScripts: Self = Self(&rules::MixedScripts);
+    pub const AmbiguousChar: Self = Self(&rules::AmbiguousChar);
+    pub const ForbiddenAccent: Self = Self(&rules::ForbiddenAccent);
+    pub const ForbiddenDoubleAccent: Self = Self(&rules::ForbiddenDoubleAccent);
+    pub const ForbiddenChar: Self = Self(&rules::ForbiddenChar);
+    pub const Punctuation: Self = Self(&rules::Punctuation);
+    pub const MissingDoubleAccentsNumeral: Self = Self(&rules::MissingDoubleAccentsNumeral);
+}
+
+/// The built-in rules.
+pub const RULES: [Rule; 15] = [
+    Rule::MissingDoubleAccents,
+    Rule::MissingAccentCapital,
+    Rule::DuplicatedWord,
+    Rule::AddFinalN,
+    Rule::RemoveFinalN,
+    Rule::OutdatedSpelling,
+    Rule::MonosyllableAccented,
+    Rule::MultisyllableNotAccented,
+    Rule::MixedScripts,
+    Rule::AmbiguousChar,
+    Rule::ForbiddenAccent,
+    Rule::ForbiddenDoubleAccent,
+    Rule::ForbiddenChar,
+    Rule::Punctuation,
+    Rule::MissingDoubleAccentsNumeral,
+];
+
+impl Rule {
+    pub const fn new(rule: &'static dyn LintRule) -> Self {
+        Self(rule)
+    }
+
+    /// The built-in rules.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        RULES.into_iter()
+    }
+
+    pub fn meta(&self) -> &'static RuleMeta {
+        self.0.meta()
+    }
+
+    pub(crate) fn checker(&self) -> &'static dyn LintRule {
+        self.0
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.meta().code
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.meta().name
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.meta().phase
+    }
+
+    pub fn group(&self) -> Group {
+        self.meta().group
+    }
+
+    pub fn has_fix(&self) -> bool {
+        self.meta().fixable
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.meta().default
+    }
+
+    pub fn is_preview(&self) -> bool {
+        self.meta().preview
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.meta().severity
+    }
+
+    pub fn requires_tokenizing(&self) -> bool {
+        self.phase() != Phase::Raw
+    }
+}
+
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for Rule {}
+
+impl Hash for Rule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state);
+    }
+}
+
+/// Old codes of rules, with the code they were replaced by.
+///
+/// They keep working, with a deprecation warning. Add an entry here whenever a
+/// code changes, and never reuse an old code.
+pub const REDIRECTS: &[(&str, &str)] = &[];
+
+/// Built-in rules by code and by name, which is accepted as an alias.
+static CODES: LazyLock<HashMap<&'static str, Rule>> = LazyLock::new(|| {
+    Rule::iter()
+        .flat_map(|rule| [(rule.code(), rule), (rule.name(), rule)])
+        .collect()
+});
+
+/// The entry of `redirects` for the old code `code`: the old code and the rule
+/// it now refers to.
+fn lookup(redirects: &[(&'static str, &str)], code: &str) -> Option<(&'static str, Rule)> {
+    let &(old, new) = redirects.iter().find(|&&(old, _)| old == code)?;
+    Some((old, *CODES.get(new)?))
 }
 
+/// The old code `code` and the rule it now refers to, if it is an old code.
+pub fn redirect(code: &str) -> Option<(&'static str, Rule)> {
+    lookup(REDIRECTS, code)
+}
+
+fn resolve(redirects: &[(&'static str, &str)], code: &str) -> Option<Rule> {
+    CODES
+        .get(code)
+        .copied()
+        .or_else(|| Some(lookup(redirects, code)?.1))
+}
+
+/// The built-in rule of a code, or of a name, following redirects.
 pub fn code_to_rule(code: &str) -> Option<Rule> {
-    Rule::iter().find(|rule| {
-        let name: &'static str = rule.into();
-        name_to_code(name) == code
-    })
+    resolve(REDIRECTS, code)
+}
+
+/// The deprecation warning of the old code `old` of `rule`.
+pub fn deprecation(old: &str, rule: Rule) -> String {
+    format!(
+        "rule code `{old}` is deprecated, use `{}` instead",
+        rule.code()
+    )
 }
 
 pub fn rule_to_name(rule: Rule) -> &'static str {
-    rule.into()
+    rule.name()
 }
 
 pub fn rule_to_code(rule: Rule) -> String {
-    name_to_code(rule_to_name(rule))
+    rule.code().to_string()
 }
 
 impl std::str::FromStr for Rule {
@@ -81,19 +285,36 @@ impl std::str::FromStr for Rule {
 
 impl std::fmt::Display for Rule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", rule_to_code(*self))
+        write!(f, "{}", self.code())
     }
 }
 
 impl std::fmt::Debug for Rule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", rule_to_name(*self))
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Rule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(serde::de::Error::custom)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::diagnostic::Message;
+    use std::collections::HashSet;
 
     #[test]
     fn converters() {
@@ -101,9 +322,130 @@ mod tests {
         let code = "MDA";
         let rule = Rule::MissingDoubleAccents;
 
-        assert_eq!(name_to_code(name), code);
         assert_eq!(code_to_rule(code), Some(rule));
         assert_eq!(rule_to_name(rule), name);
         assert_eq!(rule_to_code(rule), code);
     }
+
+    #[test]
+    fn test_aliases() {
+        assert_eq!(code_to_rule("RemoveFinalN"), Some(Rule::RemoveFinalN));
+        assert_eq!(code_to_rule("rfn"), None);
+        assert_eq!(redirect("RFN"), None);
+    }
+
+    #[test]
+    fn test_redirects() {
+        let redirects = &[("RMFN", "RFN"), ("XYZ", "UNKNOWN")];
+        assert_eq!(resolve(redirects, "RMFN"), Some(Rule::RemoveFinalN));
+        assert_eq!(resolve(redirects, "RFN"), Some(Rule::RemoveFinalN));
+        assert_eq!(resolve(redirects, "XYZ"), None);
+        assert_eq!(
+            lookup(redirects, "RMFN"),
+            Some(("RMFN", Rule::RemoveFinalN))
+        );
+        assert_eq!(lookup(redirects, "RFN"), None);
+        assert_eq!(
+            deprecation("RMFN", Rule::RemoveFinalN),
+            "rule code `RMFN` is deprecated, use `RFN` instead"
+        );
+    }
+
+    #[test]
+    fn test_unique_codes() {
+        let mut seen = HashSet::new();
+        for rule in Rule::iter() {
+            let code = rule.code();
+            assert!(
+                !code.is_empty() && code.chars().all(|c| c.is_ascii_uppercase()),
+                "invalid code: {code}"
+            );
+            assert!(seen.insert(code), "duplicated code: {code}");
+            assert!(seen.insert(rule.name()), "duplicated name: {}", rule.name());
+        }
+        for &(old, new) in REDIRECTS {
+            assert!(seen.insert(old), "redirect of a current code: {old}");
+            assert!(
+                CODES.contains_key(new),
+                "redirect to an unknown code: {new}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_groups() {
+        let codes = |group: Group| group.rules().map(|rule| rule.code()).collect::<Vec<_>>();
+        assert_eq!(
+            codes(Group::Accent),
+            ["MDA", "MAC", "MA", "MNA", "FA", "FDA", "MDAN"]
+        );
+        assert_eq!(codes(Group::Char), ["MS", "AC", "FC"]);
+        assert_eq!(codes(Group::Grammar), ["DW", "AFN", "RFN"]);
+        assert_eq!(codes(Group::Typography), ["OS", "P"]);
+        assert_eq!("char".parse(), Ok(Group::Char));
+    }
+
+    #[test]
+    fn test_phases() {
+        assert!(!Rule::OutdatedSpelling.requires_tokenizing());
+        assert!(Rule::RemoveFinalN.requires_tokenizing());
+        assert!(!Rule::DuplicatedWord.has_fix());
+    }
+
+    #[test]
+    fn test_default_severities() {
+        assert_eq!(Rule::ForbiddenChar.severity(), Severity::Error);
+        assert_eq!(Rule::MultisyllableNotAccented.severity(), Severity::Warning);
+        assert_eq!(Rule::DuplicatedWord.severity(), Severity::Info);
+    }
+
+    /// A rule defined outside of the built-in ones.
+    struct Shouting;
+
+    impl LintRule for Shouting {
+        fn meta(&self) -> &'static RuleMeta {
+            &RuleMeta {
+                code: "SH",
+                name: "Shouting",
+                phase: Phase::Sentence,
+                group: Group::Typography,
+                fixable: false,
+                default: false,
+                preview: false,
+                severity: Severity::Info,
+                docs: "Sentences ending with several exclamation marks.",
+            }
+        }
+
+        fn check_sentence(
+            &self,
+            sentence: &[Token],
+            _doc: &Doc,
+            diagnostics: &mut Vec<Diagnostic>,
+        ) {
+            if let Some(last) = sentence.last()
+                && last.text().matches('!').count() > 1
+            {
+                diagnostics.push(Diagnostic {
+                    kind: Rule::new(&Shouting),
+                    range: last.range(),
+                    suggestions: Vec::new(),
+                    severity: Rule::new(&Shouting).severity(),
+                    message: Message::new(
+                        "πολλά θαυμαστικά".to_string(),
+                        "too many exclamation marks".to_string(),
+                    ),
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        let shouting = Rule::new(&Shouting);
+        assert_eq!(code_to_rule("SH"), None);
+        let diagnostics = crate::linter::check("Ναι!! Όχι.", &[shouting, Rule::RemoveFinalN]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, shouting);
+    }
 }
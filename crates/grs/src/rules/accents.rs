@@ -1,5 +1,5 @@
-use crate::diagnostic::{Diagnostic, Fix};
-use crate::registry::Rule;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::rules::forbidden_accent::CORRECT_MULTISYLLABLE_NOT_ACCENTED;
 use crate::tokenizer::{Doc, Token};
 use grac::constants::{APOSTROPHES, MONOSYLLABLE_ACCENTED_WITH_PRONOUNS};
@@ -59,10 +59,25 @@ pub fn monosyllable_accented(token: &Token, doc: &Doc, diagnostics: &mut Vec<Dia
         diagnostics.push(Diagnostic {
             kind: Rule::MonosyllableAccented,
             range: token.range(),
-            fix: Some(Fix {
-                replacement: without_accent,
-                range: token.range(),
-            }),
+            suggestions: vec![Suggestion::preferred(
+                Message::new(
+                    "αφαίρεση του τόνου".to_string(),
+                    "remove the accent".to_string(),
+                ),
+                Fix::edit(Edit::diff(
+                    token.range().start(),
+                    token.text(),
+                    &without_accent,
+                )),
+            )],
+            severity: Rule::MonosyllableAccented.severity(),
+            message: Message::new(
+                format!("το μονοσύλλαβο «{}» δεν τονίζεται εδώ", token.text()),
+                format!(
+                    "monosyllable «{}» should not carry an accent here",
+                    token.text()
+                ),
+            ),
         });
     }
 }
@@ -135,11 +150,60 @@ pub fn multisyllable_not_accented(token: &Token, doc: &Doc, diagnostics: &mut Ve
         diagnostics.push(Diagnostic {
             kind: Rule::MultisyllableNotAccented,
             range: token.range(),
-            fix: None,
+            suggestions: Vec::new(),
+            severity: Rule::MultisyllableNotAccented.severity(),
+            message: Message::new(
+                format!("η πολυσύλλαβη λέξη «{}» δεν έχει τόνο", token.text()),
+                format!("multisyllable word «{}» has no accent", token.text()),
+            ),
         });
     }
 }
 
+pub struct MonosyllableAccented;
+
+impl LintRule for MonosyllableAccented {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "MA",
+            name: "MonosyllableAccented",
+            phase: Phase::Token,
+            group: Group::Accent,
+            fixable: true,
+            default: true,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Accented monosyllables, ex. ποιός.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        monosyllable_accented(token, doc, diagnostics);
+    }
+}
+
+pub struct MultisyllableNotAccented;
+
+impl LintRule for MultisyllableNotAccented {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "MNA",
+            name: "MultisyllableNotAccented",
+            phase: Phase::Token,
+            group: Group::Accent,
+            fixable: false,
+            default: true,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Words of several syllables without accent, ex. καλημερα.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        multisyllable_not_accented(token, doc, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,6 @@
-use crate::diagnostic::{Diagnostic, Fix};
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
 use crate::range::TextRange;
-use crate::registry::Rule;
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 
 // It is easier to work with strings, but these should really be chars
 const AMBIGUOUS_PAIRS: [(&str, &str); 1] = [("µ", "μ")];
@@ -9,18 +9,51 @@ pub fn ambiguous_char(text: &str, diagnostics: &mut Vec<Diagnostic>) {
     for (target, destination) in AMBIGUOUS_PAIRS {
         if let Some((start, _)) = text.match_indices(target).next() {
             let range = TextRange::new(start, start + target.len());
+            let label = Message::new(
+                format!("αντικατάσταση με το ελληνικό «{destination}»"),
+                format!("replace with the Greek «{destination}»"),
+            );
+            let fix = Fix::edit(Edit::replacement(destination.to_string(), range));
             diagnostics.push(Diagnostic {
                 kind: Rule::AmbiguousChar,
                 range,
-                fix: Some(Fix {
-                    replacement: destination.to_string(),
-                    range,
-                }),
+                suggestions: vec![Suggestion::preferred(label, fix)],
+                severity: Rule::AmbiguousChar.severity(),
+                message: Message::new(
+                    format!(
+                        "το «{target}» μοιάζει με το ελληνικό «{destination}» αλλά είναι άλλος χαρακτήρας"
+                    ),
+                    format!(
+                        "«{target}» looks like the Greek «{destination}» but is another character"
+                    ),
+                ),
             });
         }
     }
 }
 
+pub struct AmbiguousChar;
+
+impl LintRule for AmbiguousChar {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "AC",
+            name: "AmbiguousChar",
+            phase: Phase::Raw,
+            group: Group::Char,
+            fixable: true,
+            default: false,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Characters mistaken for Greek ones, ex. the micro sign µ.",
+        }
+    }
+
+    fn check_raw(&self, text: &str, diagnostics: &mut Vec<Diagnostic>) {
+        ambiguous_char(text, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,6 @@
-use crate::diagnostic::Diagnostic;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
 use crate::range::TextRange;
-use crate::registry::Rule;
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::rules::missing_double_accents::PRONOUNS_LOWERCASE;
 use crate::tokenizer::{Doc, Token};
 
@@ -55,10 +55,12 @@ fn duplicated_word_opt<'a>(token: &Token, doc: &'a Doc) -> Option<&'a Token<'a>>
 
 /// Detect duplicated words.
 ///
-/// Unfixable: removing the duplicated word may not be the intended approach,
-/// sometimes what is needed is extra punctuation:
+/// No preferred fix: removing the duplicated word may not be the intended
+/// approach, sometimes what is needed is extra punctuation:
 /// * '— Τζωρτζ Τζωρτζ.' > '— Τζωρτζ, Τζωρτζ!'
 ///
+/// Both are suggested, in that order.
+///
 /// It is also not clear what to do with consecutive duplications:
 /// * το το το
 ///
@@ -67,14 +69,54 @@ fn duplicated_word_opt<'a>(token: &Token, doc: &'a Doc) -> Option<&'a Token<'a>>
 pub fn duplicated_word(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
     if let Some(ntoken) = duplicated_word_opt(token, doc) {
         let range = TextRange::new(token.range().start(), ntoken.range().end());
+        let end = token.range().end();
         diagnostics.push(Diagnostic {
             kind: Rule::DuplicatedWord,
             range,
-            fix: None,
+            suggestions: vec![
+                Suggestion::alternative(
+                    Message::new(
+                        "διαγραφή της επανάληψης".to_string(),
+                        "delete the repetition".to_string(),
+                    ),
+                    Fix::edit(Edit::deletion(TextRange::new(end, ntoken.range().end()))),
+                ),
+                Suggestion::alternative(
+                    Message::new("προσθήκη κόμματος".to_string(), "add a comma".to_string()),
+                    Fix::edit(Edit::insertion(",".to_string(), end)),
+                ),
+            ],
+            severity: Rule::DuplicatedWord.severity(),
+            message: Message::new(
+                format!("η λέξη «{}» επαναλαμβάνεται", token.text()),
+                format!("the word «{}» is repeated", token.text()),
+            ),
         });
     }
 }
 
+pub struct DuplicatedWord;
+
+impl LintRule for DuplicatedWord {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "DW",
+            name: "DuplicatedWord",
+            phase: Phase::Token,
+            group: Group::Grammar,
+            fixable: false,
+            default: false,
+            preview: false,
+            severity: Severity::Info,
+            docs: "Repeated words, ex. το το.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        duplicated_word(token, doc, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,4 +142,26 @@ mod tests {
 
     // Ignore spacing emphasis, i.e. s p a c i n g
     test_dw!(spacing, "Ω σ α ν ν ά", true);
+
+    #[test]
+    fn test_suggestions() {
+        let text = "Τζωρτζ Τζωρτζ.";
+        let doc = crate::tokenizer::tokenize(text);
+        let mut diagnostics = Vec::new();
+        duplicated_word(&doc[0], &doc, &mut diagnostics);
+        let diagnostic = &diagnostics[0];
+        assert!(diagnostic.fix().is_none());
+        let labels: Vec<_> = diagnostic
+            .suggestions
+            .iter()
+            .map(|suggestion| suggestion.label.get(crate::diagnostic::Lang::En))
+            .collect();
+        assert_eq!(labels, ["delete the repetition", "add a comma"]);
+        let fixed: Vec<_> = diagnostic
+            .suggestions
+            .iter()
+            .map(|suggestion| suggestion.fix.apply(text))
+            .collect();
+        assert_eq!(fixed, ["Τζωρτζ.", "Τζωρτζ, Τζωρτζ."]);
+    }
 }
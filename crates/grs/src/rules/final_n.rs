@@ -1,5 +1,6 @@
-use crate::diagnostic::{Diagnostic, Fix};
-use crate::registry::Rule;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
+use crate::range::TextRange;
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::tokenizer::{Doc, Token};
 use grac::is_vowel;
 
@@ -21,16 +22,24 @@ fn remove_last_char(s: &str) -> &str {
     chars.as_str()
 }
 
+/// The vowel or plosive (cluster) that starts the token, if any.
+fn vowel_or_plosive_prefix<'t>(token: &'t Token<'_>) -> Option<&'t str> {
+    let text = token.text();
+    let ch = text.chars().next()?;
+    PLOSIVE_CLUSTERS
+        .iter()
+        .filter(|&&prefix| text.starts_with(prefix))
+        .max_by_key(|prefix| prefix.len())
+        .copied()
+        .or_else(|| is_vowel(ch).then(|| &text[..ch.len_utf8()]))
+}
+
 fn starts_with_vowel_or_plosive(token: &Token) -> bool {
-    token.text().chars().next().is_some_and(|ch| {
-        PLOSIVE_CLUSTERS
-            .iter()
-            .any(|&prefix| token.text().starts_with(prefix))
-            || is_vowel(ch)
-    })
+    vowel_or_plosive_prefix(token).is_some()
 }
 
-fn remove_final_n_opt(token: &Token, doc: &Doc) -> Option<()> {
+/// Return the first letter of the next word if the final ν must be removed.
+fn remove_final_n_opt(token: &Token, doc: &Doc) -> Option<char> {
     if CANDIDATES_REM.contains(&token.text()) {
         // Treat archaic construction "εις την" as valid
         if token.text() == "την"
@@ -44,27 +53,40 @@ fn remove_final_n_opt(token: &Token, doc: &Doc) -> Option<()> {
             && ntoken.is_greek_word()
             && !starts_with_vowel_or_plosive(ntoken)
         {
-            return Some(());
+            return ntoken.text().chars().next();
         }
     }
     None
 }
 
 pub fn remove_final_n(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
-    if remove_final_n_opt(token, doc).is_some() {
-        let replacement = remove_last_char(token.text()).to_string();
+    if let Some(next) = remove_final_n_opt(token, doc) {
+        let kept = remove_last_char(token.text()).len();
+        let range = token.range();
         diagnostics.push(Diagnostic {
             kind: Rule::RemoveFinalN,
             range: token.range(),
-            fix: Some(Fix {
-                replacement,
-                range: token.range(),
-            }),
+            suggestions: vec![Suggestion::preferred(
+                Message::new(
+                    "αφαίρεση του τελικού -ν".to_string(),
+                    "remove the final -ν".to_string(),
+                ),
+                Fix::edit(Edit::deletion(TextRange::new(
+                    range.start() + kept,
+                    range.end(),
+                ))),
+            )],
+            severity: Rule::RemoveFinalN.severity(),
+            message: Message::new(
+                format!("τελικό -ν πρέπει να παραλείπεται πριν από «{next}»"),
+                format!("final -ν should be omitted before «{next}»"),
+            ),
         });
     }
 }
 
-fn add_final_n_opt(token: &Token, doc: &Doc) -> Option<()> {
+/// Return the vowel or plosive starting the next word if a final ν must be added.
+fn add_final_n_opt<'a>(token: &Token, doc: &'a Doc) -> Option<&'a str> {
     if CANDIDATES_ADD.contains(&token.text()) {
         // Treat archaic construction "εν τη" as valid
         if token.text() == "τη"
@@ -75,7 +97,7 @@ fn add_final_n_opt(token: &Token, doc: &Doc) -> Option<()> {
         }
 
         let ntoken = doc.next_token_not_whitespace(token)?;
-        if starts_with_vowel_or_plosive(ntoken) {
+        if let Some(prefix) = vowel_or_plosive_prefix(ntoken) {
             // To avoid false positives in case of formal expressions
             // with dative (Ex. επί τη εμφανίσει OR πρώτος τη τάξει),
             // we return None in case ntoken ends with ει.
@@ -83,7 +105,7 @@ fn add_final_n_opt(token: &Token, doc: &Doc) -> Option<()> {
             return if ntoken.text().ends_with("ει") {
                 None
             } else {
-                Some(())
+                Some(prefix)
             };
         }
     }
@@ -92,18 +114,70 @@ fn add_final_n_opt(token: &Token, doc: &Doc) -> Option<()> {
 }
 
 pub fn add_final_n(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
-    if add_final_n_opt(token, doc).is_some() {
+    if let Some(next) = add_final_n_opt(token, doc) {
         diagnostics.push(Diagnostic {
             kind: Rule::AddFinalN,
             range: token.range(),
-            fix: Some(Fix {
-                replacement: format!("{}ν", token.text()),
-                range: token.range(),
-            }),
+            suggestions: vec![Suggestion::preferred(
+                Message::new(
+                    "προσθήκη τελικού -ν".to_string(),
+                    "add a final -ν".to_string(),
+                ),
+                Fix::edit(Edit::insertion("ν".to_string(), token.range().end())),
+            )],
+            severity: Rule::AddFinalN.severity(),
+            message: Message::new(
+                format!("τελικό -ν πρέπει να διατηρείται πριν από «{next}»"),
+                format!("final -ν should be kept before «{next}»"),
+            ),
         });
     }
 }
 
+pub struct AddFinalN;
+
+impl LintRule for AddFinalN {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "AFN",
+            name: "AddFinalN",
+            phase: Phase::Token,
+            group: Group::Grammar,
+            fixable: true,
+            default: false,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Missing final ν before a vowel or a plosive, ex. τη μπάλα.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        add_final_n(token, doc, diagnostics);
+    }
+}
+
+pub struct RemoveFinalN;
+
+impl LintRule for RemoveFinalN {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "RFN",
+            name: "RemoveFinalN",
+            phase: Phase::Token,
+            group: Group::Grammar,
+            fixable: true,
+            default: false,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Superfluous final ν before other consonants, ex. την δώσεις.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        remove_final_n(token, doc, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,4 +207,27 @@ mod tests {
     test_remove!(remove_punct1, "Πιάστε την! Για τον θεό", true);
     test_remove!(remove_punct2, "Πιάστε την, για τον θεό", true);
     test_remove!(remove_ignore_eis, "εις την θάλασσαν", true);
+
+    #[test]
+    fn test_messages() {
+        use crate::diagnostic::Lang;
+        use crate::linter::check;
+
+        let diagnostics = check("στην δόξα", &[Rule::RemoveFinalN]);
+        let message = &diagnostics[0].message;
+        assert_eq!(
+            message.get(Lang::El),
+            "τελικό -ν πρέπει να παραλείπεται πριν από «δ»"
+        );
+        assert_eq!(
+            message.get(Lang::En),
+            "final -ν should be omitted before «δ»"
+        );
+
+        let diagnostics = check("στη ψυχή", &[Rule::AddFinalN]);
+        assert_eq!(
+            diagnostics[0].message.get(Lang::En),
+            "final -ν should be kept before «ψ»"
+        );
+    }
 }
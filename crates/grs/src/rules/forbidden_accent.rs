@@ -1,5 +1,5 @@
-use crate::diagnostic::{Diagnostic, Fix};
-use crate::registry::Rule;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::rules::missing_double_accents::PRONOUNS_LOWERCASE;
 use crate::tokenizer::{Doc, Token};
 use grac::{
@@ -138,12 +138,24 @@ pub fn forbidden_accent(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnost
         diagnostics.push(Diagnostic {
             kind: Rule::ForbiddenAccent,
             range: token.range(),
-            fix: None,
+            suggestions: Vec::new(),
+            severity: Rule::ForbiddenAccent.severity(),
+            message: Message::new(
+                format!(
+                    "η «{}» έχει τόνο σε συλλαβή που δεν τονίζεται",
+                    token.text()
+                ),
+                format!(
+                    "«{}» has an accent on a syllable that can not take one",
+                    token.text()
+                ),
+            ),
         });
     }
 }
 
-fn forbidden_double_accent_opt(token: &Token, doc: &Doc) -> Option<()> {
+/// Return the next word if the second accent of the token is superfluous.
+fn forbidden_double_accent_opt<'a>(token: &Token, doc: &'a Doc) -> Option<&'a Token<'a>> {
     // This is separate from forbidden accent because it is fixable
     debug_assert!(token.is_greek_word());
 
@@ -169,7 +181,7 @@ fn forbidden_double_accent_opt(token: &Token, doc: &Doc) -> Option<()> {
             return if ALLOWED_WORDS_AFTER_DOUBLE_ACCENT.contains(&ntoken.text()) {
                 None
             } else {
-                Some(())
+                Some(ntoken)
             };
         }
     }
@@ -178,19 +190,85 @@ fn forbidden_double_accent_opt(token: &Token, doc: &Doc) -> Option<()> {
 }
 
 pub fn forbidden_double_accent(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
-    if token.is_greek_word() && forbidden_double_accent_opt(token, doc).is_some() {
+    if token.is_greek_word()
+        && let Some(ntoken) = forbidden_double_accent_opt(token, doc)
+    {
         let without_accent = remove_diacritic_at(token.text(), 1, Diacritic::ACUTE);
         diagnostics.push(Diagnostic {
             kind: Rule::ForbiddenDoubleAccent,
             range: token.range(),
-            fix: Some(Fix {
-                replacement: without_accent,
-                range: token.range(),
-            }),
+            suggestions: vec![Suggestion::preferred(
+                Message::new(
+                    "αφαίρεση του δεύτερου τόνου".to_string(),
+                    "remove the second accent".to_string(),
+                ),
+                Fix::edit(Edit::diff(
+                    token.range().start(),
+                    token.text(),
+                    &without_accent,
+                )),
+            )],
+            severity: Rule::ForbiddenDoubleAccent.severity(),
+            message: Message::new(
+                format!(
+                    "η «{}» δεν παίρνει δεύτερο τόνο πριν από «{}»",
+                    token.text(),
+                    ntoken.text()
+                ),
+                format!(
+                    "«{}» should not take a second accent before «{}»",
+                    token.text(),
+                    ntoken.text()
+                ),
+            ),
         });
     }
 }
 
+pub struct ForbiddenAccent;
+
+impl LintRule for ForbiddenAccent {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "FA",
+            name: "ForbiddenAccent",
+            phase: Phase::Token,
+            group: Group::Accent,
+            fixable: false,
+            default: false,
+            preview: false,
+            severity: Severity::Error,
+            docs: "Accents on syllables that can not take one.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        forbidden_accent(token, doc, diagnostics);
+    }
+}
+
+pub struct ForbiddenDoubleAccent;
+
+impl LintRule for ForbiddenDoubleAccent {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "FDA",
+            name: "ForbiddenDoubleAccent",
+            phase: Phase::Token,
+            group: Group::Accent,
+            fixable: true,
+            default: false,
+            preview: false,
+            severity: Severity::Error,
+            docs: "Superfluous second accents.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        forbidden_double_accent(token, doc, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,12 +1,10 @@
-use crate::diagnostic::Diagnostic;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
 use crate::range::TextRange;
-use crate::registry::Rule;
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use grac::is_greek_letter;
 
 // Check if the char after ς is allowed.
 const fn wrong_after_sigma(c: char) -> bool {
-    // For testing against the wiki dumps, uncomment this line.
-    // c != 'Ο' && c != 'Γ' && c != 'ς' &&
     is_greek_letter(c)
 }
 
@@ -14,9 +12,11 @@ const fn wrong_after_sigma(c: char) -> bool {
 ///
 /// Identify:
 /// * "ς" not in final position.
-/// > There is no fix since it could be caused by either:
+/// > There is no preferred fix since it could be caused by either:
 ///   * A simple confusion of ς and σ: πιςτεύοντας
 ///   * A missing space: πιστεύονταςτην
+///
+///   Both are suggested, in that order.
 /// * Accents on non vowels.
 /// > TODO:
 pub fn forbidden_char(text: &str, diagnostics: &mut Vec<Diagnostic>) {
@@ -28,7 +28,27 @@ pub fn forbidden_char(text: &str, diagnostics: &mut Vec<Diagnostic>) {
                 diagnostics.push(Diagnostic {
                     kind: Rule::ForbiddenChar,
                     range,
-                    fix: None,
+                    suggestions: vec![
+                        Suggestion::alternative(
+                            Message::new(
+                                "αντικατάσταση του «ς» με «σ»".to_string(),
+                                "replace «ς» with «σ»".to_string(),
+                            ),
+                            Fix::edit(Edit::replacement("σ".to_string(), range)),
+                        ),
+                        Suggestion::alternative(
+                            Message::new(
+                                "προσθήκη κενού μετά το «ς»".to_string(),
+                                "add a space after «ς»".to_string(),
+                            ),
+                            Fix::edit(Edit::insertion(" ".to_string(), range.end())),
+                        ),
+                    ],
+                    severity: Rule::ForbiddenChar.severity(),
+                    message: Message::new(
+                        format!("τελικό «ς» μέσα σε λέξη, πριν από «{c2}»"),
+                        format!("final «ς» inside a word, before «{c2}»"),
+                    ),
                 });
             }
             idx1 = idx2;
@@ -36,3 +56,46 @@ pub fn forbidden_char(text: &str, diagnostics: &mut Vec<Diagnostic>) {
         }
     }
 }
+
+pub struct ForbiddenChar;
+
+impl LintRule for ForbiddenChar {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "FC",
+            name: "ForbiddenChar",
+            phase: Phase::Raw,
+            group: Group::Char,
+            fixable: false,
+            default: false,
+            preview: false,
+            severity: Severity::Error,
+            docs: "Characters that can not appear in Greek words, ex. a final ς inside a word.",
+        }
+    }
+
+    fn check_raw(&self, text: &str, diagnostics: &mut Vec<Diagnostic>) {
+        forbidden_char(text, diagnostics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions() {
+        let text = "πιςτεύοντας";
+        let mut diagnostics = Vec::new();
+        forbidden_char(text, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert!(diagnostic.fix().is_none());
+        let fixed: Vec<_> = diagnostic
+            .suggestions
+            .iter()
+            .map(|suggestion| suggestion.fix.apply(text))
+            .collect();
+        assert_eq!(fixed, ["πιστεύοντας", "πις τεύοντας"]);
+    }
+}
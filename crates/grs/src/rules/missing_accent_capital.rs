@@ -2,8 +2,8 @@
 // sentence does not take accent if it happened to be on it's first letter (a vowel).
 // Ex. Ηταν μόλις 31…
 
-use crate::diagnostic::{Diagnostic, Fix};
-use crate::registry::Rule;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::rules::accents::is_protaktiko;
 use crate::tokenizer::{Doc, Token};
 use grac::{add_acute_at, has_any_diacritic, is_vowel};
@@ -50,15 +50,52 @@ pub fn missing_accent_capital(token: &Token, doc: &Doc, diagnostics: &mut Vec<Di
             diagnostics.push(Diagnostic {
                 kind: Rule::MissingAccentCapital,
                 range: token.range(),
-                fix: Some(Fix {
-                    replacement: add_acute_at(token.text(), n_syllables),
-                    range: token.range(),
-                }),
+                suggestions: vec![Suggestion::preferred(
+                    Message::new("προσθήκη τόνου".to_string(), "add an accent".to_string()),
+                    Fix::edit(Edit::diff(
+                        token.range().start(),
+                        token.text(),
+                        &add_acute_at(token.text(), n_syllables),
+                    )),
+                )],
+                severity: Rule::MissingAccentCapital.severity(),
+                message: Message::new(
+                    format!(
+                        "λείπει ο τόνος από το αρχικό κεφαλαίο της «{}»",
+                        token.text()
+                    ),
+                    format!(
+                        "missing accent on the capital initial of «{}»",
+                        token.text()
+                    ),
+                ),
             });
         }
     }
 }
 
+pub struct MissingAccentCapital;
+
+impl LintRule for MissingAccentCapital {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "MAC",
+            name: "MissingAccentCapital",
+            phase: Phase::Token,
+            group: Group::Accent,
+            fixable: true,
+            default: false,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Capitalized words missing their accent, ex. Ηταν.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        missing_accent_capital(token, doc, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3,8 +3,8 @@
 // While rare enough, the current logic contains false positives. Ex:
 // * και το κτήριο του, παλαιού πλέον, Μουσείου Ακρόπολης
 
-use crate::diagnostic::{Diagnostic, Fix};
-use crate::registry::Rule;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::tokenizer::{Doc, Token};
 use grac::Diacritic;
 use grac::add_acute_at;
@@ -72,6 +72,10 @@ const STOKEN_SEPARATOR_WORDS: [&str; 51] = [
     "με", "χωρίς", "θα", "για", "δεν",
 ];
 
+const GENITIVE_PRONOUNS: [&str; 6] = ["μου", "σου", "του", "της", "μας", "σας"];
+
+const NUMERALS: [&str; 7] = ["μια", "ένα", "έναν", "δυο", "δύο", "τρία", "τρια"];
+
 // Does not include το, τα since they can also be accusative
 const ARTICLE_NOMINATIVE: [&str; 3] = ["ο", "η", "οι"];
 
@@ -92,6 +96,16 @@ fn lemmatize(s: &str) -> &str {
     s.trim_end_matches('ν').trim_start_matches("παλιο")
 }
 
+/// Words for which we can not tell whether a double accent is missing.
+fn is_ambiguous(token: &Token) -> bool {
+    MULTIPLE_PRONUNCIATION.contains(&token.text())
+        // We do not deal with diminutives at the moment.
+        || token.text().ends_with("άκια")
+        || token.text().ends_with("ούδια")
+        // See also `crate::rules::accents::multisyllable_not_accented_opt`
+        || token.text().contains(['[', ']'])
+}
+
 /// Return true iif we need to fix the missing double accent.
 ///
 /// The proparoxytone test is very expensive, so we compute it last, outside of this function.
@@ -114,13 +128,7 @@ fn missing_double_accents_opt(token: &Token, doc: &Doc) -> Option<()> {
         return None;
     }
 
-    if MULTIPLE_PRONUNCIATION.contains(&token.text())
-        // We do not deal with diminutives at the moment.
-        || token.text().ends_with("άκια")
-        || token.text().ends_with("ούδια")
-        // See also `crate::rules::accents::multisyllable_not_accented_opt`
-        || token.text().contains(['[', ']'])
-    {
+    if is_ambiguous(token) {
         return None;
     }
 
@@ -166,30 +174,113 @@ fn missing_double_accents_opt(token: &Token, doc: &Doc) -> Option<()> {
         return Some(());
     }
 
-    // Testing
-    // > δίνοντας μου μια μπατσιά στη ράχη
-    // if ["μου", "σου", "του", "της", "μας", "σας"].contains(&ntoken.text())
-    //     && ["μια", "ένα", "έναν", "δυο", "δύο", "τρία", "τρια"].contains(&nntoken.text())
-    // {
-    //     return Some(());
-    // }
-
     None
 }
 
+fn push_diagnostic(rule: Rule, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+    let enclitic = doc
+        .next_token_not_whitespace(token)
+        .map_or("", |ntoken| ntoken.text());
+    diagnostics.push(Diagnostic {
+        kind: rule,
+        range: token.range(),
+        suggestions: vec![Suggestion::preferred(
+            Message::new(
+                "προσθήκη δεύτερου τόνου".to_string(),
+                "add a second accent".to_string(),
+            ),
+            Fix::edit(Edit::diff(
+                token.range().start(),
+                token.text(),
+                &add_acute_at(token.text(), 1),
+            )),
+        )],
+        severity: rule.severity(),
+        message: Message::new(
+            format!(
+                "η «{}» παίρνει δεύτερο τόνο πριν από το εγκλιτικό «{enclitic}»",
+                token.text()
+            ),
+            format!(
+                "«{}» needs a second accent before the enclitic «{enclitic}»",
+                token.text()
+            ),
+        ),
+    });
+}
+
 pub fn missing_double_accents(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
     if token.is_greek_word()
         && missing_double_accents_opt(token, doc).is_some()
         && is_proparoxytone_strict(lemmatize(token.text()))
     {
-        diagnostics.push(Diagnostic {
-            kind: Rule::MissingDoubleAccents,
-            range: token.range(),
-            fix: Some(Fix {
-                replacement: add_acute_at(token.text(), 1),
-                range: token.range(),
-            }),
-        });
+        push_diagnostic(Rule::MissingDoubleAccents, token, doc, diagnostics);
+    }
+}
+
+/// Return true iif a double accent is missing before a pronoun followed by a
+/// numeral. Experimental: the numeral may start the object of the verb.
+///
+/// Ex.  δίνοντας μου μια μπατσιά στη ράχη
+fn missing_double_accents_numeral_opt(token: &Token, doc: &Doc) -> Option<()> {
+    let ntoken = doc.next_token_not_whitespace(token)?;
+    if !GENITIVE_PRONOUNS.contains(&ntoken.text()) || is_ambiguous(token) {
+        return None;
+    }
+    let nntoken = doc.next_token_not_whitespace(ntoken)?;
+    NUMERALS.contains(&nntoken.text()).then_some(())
+}
+
+pub fn missing_double_accents_numeral(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+    if token.is_greek_word()
+        && missing_double_accents_numeral_opt(token, doc).is_some()
+        && is_proparoxytone_strict(lemmatize(token.text()))
+    {
+        push_diagnostic(Rule::MissingDoubleAccentsNumeral, token, doc, diagnostics);
+    }
+}
+
+pub struct MissingDoubleAccents;
+
+impl LintRule for MissingDoubleAccents {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "MDA",
+            name: "MissingDoubleAccents",
+            phase: Phase::Token,
+            group: Group::Accent,
+            fixable: true,
+            default: true,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Missing second accent before an enclitic, ex. ανακαλύφθηκε το.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        missing_double_accents(token, doc, diagnostics);
+    }
+}
+
+pub struct MissingDoubleAccentsNumeral;
+
+impl LintRule for MissingDoubleAccentsNumeral {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "MDAN",
+            name: "MissingDoubleAccentsNumeral",
+            phase: Phase::Token,
+            group: Group::Accent,
+            fixable: true,
+            default: false,
+            preview: true,
+            severity: Severity::Warning,
+            docs: "Missing second accent before a pronoun and a numeral, ex. δίνοντας μου μια.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        missing_double_accents_numeral(token, doc, diagnostics);
     }
 }
 
@@ -204,6 +295,12 @@ mod tests {
         };
     }
 
+    macro_rules! test_mdan {
+        ($name:ident, $text:expr, $expected:expr) => {
+            test_rule!($name, missing_double_accents_numeral, $text, $expected);
+        };
+    }
+
     test_mda!(basic1, "ανακαλύφθηκε το.", false);
     test_mda!(basic2, "Όταν ανακαλύφθηκε το.", false);
 
@@ -276,4 +373,10 @@ mod tests {
 
     // Experimental
     test_mda!(synizesis, "Στάσου, έννοια σου!", true);
+
+    // Numerals (preview)
+    test_mda!(numeral_stable, "δίνοντας μου μια μπατσιά", true);
+    test_mdan!(numeral1, "δίνοντας μου μια μπατσιά", false);
+    test_mdan!(numeral2, "είπε μου δυο λόγια", true);
+    test_mdan!(numeral3, "δίνοντάς μου μια μπατσιά", true);
 }
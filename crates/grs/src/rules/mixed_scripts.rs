@@ -1,5 +1,6 @@
-use crate::diagnostic::{Diagnostic, Fix};
-use crate::registry::Rule;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
+use crate::range::TextRange;
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::tokenizer::{Doc, Token};
 use grac::is_greek_char;
 
@@ -59,26 +60,67 @@ fn mixed_scripts_opt(token: &Token) -> Option<()> {
 /// Ex. νέo (the o is the latin letter o)
 pub fn mixed_scripts(token: &Token, _doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
     if mixed_scripts_opt(token).is_some() {
-        let mut replacement = String::new();
-        for ch in token.text().chars() {
+        let mut edits = Vec::new();
+        let mut latin = String::new();
+        for (idx, ch) in token.text().char_indices() {
             let fixed_ch = LATIN_TO_GREEK
                 .iter()
                 .find_map(|(latin, greek)| if ch == *latin { Some(greek) } else { None })
                 .unwrap_or(&ch);
-            replacement.push(*fixed_ch);
+            if *fixed_ch != ch {
+                latin.push(ch);
+                let start = token.range().start() + idx;
+                let range = TextRange::new(start, start + ch.len_utf8());
+                edits.push(Edit::replacement(fixed_ch.to_string(), range));
+            }
         }
 
         diagnostics.push(Diagnostic {
             kind: Rule::MixedScripts,
             range: token.range(),
-            fix: Some(Fix {
-                replacement,
-                range: token.range(),
-            }),
+            suggestions: vec![Suggestion::preferred(
+                Message::new(
+                    "αντικατάσταση των λατινικών χαρακτήρων".to_string(),
+                    "replace the Latin characters".to_string(),
+                ),
+                Fix::new(edits),
+            )],
+            severity: Rule::MixedScripts.severity(),
+            message: Message::new(
+                format!("η «{}» έχει λατινικούς χαρακτήρες: «{latin}»", token.text()),
+                format!("«{}» contains Latin characters: «{latin}»", token.text()),
+            ),
         });
     }
 }
 
+pub struct MixedScripts;
+
+impl LintRule for MixedScripts {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "MS",
+            name: "MixedScripts",
+            phase: Phase::Document,
+            group: Group::Char,
+            fixable: true,
+            default: false,
+            preview: false,
+            severity: Severity::Error,
+            docs: "Greek words with Latin letters, ex. νέo with a Latin o.",
+        }
+    }
+
+    fn check_document(&self, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        // Words with Latin letters are not Greek words for the tokenizer.
+        for token in doc.iter().filter(|token| {
+            !token.is_whitespace() && !token.is_punctuation() && !token.is_greek_word()
+        }) {
+            mixed_scripts(token, doc, diagnostics);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -98,6 +140,15 @@ mod test {
 
     // The first char is an uppercase "a"
     test_fix_ms!(mixed_scripts_fix, "Aλλά", "Αλλά");
+    // Two edits, for the "A" and the "o".
+    test_fix_ms!(mixed_scripts_fix_edits, "Aλλo", "Αλλο");
+    // Once fixed, λλά is not a monosyllable.
+    test_fix!(
+        mixed_scripts_fix_with_accents,
+        &[Rule::MixedScripts, Rule::MonosyllableAccented],
+        "Το Aλλά",
+        "Το Αλλά"
+    );
 
     test_ms!(mixed_scripts_ok, "τίποτα", true);
     test_ms!(mixed_scripts_only_latin, "aou", true);
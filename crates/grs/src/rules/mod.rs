@@ -13,15 +13,23 @@ mod ambiguous_char;
 mod forbidden_char;
 mod outdated_spelling;
 
-pub use accents::{monosyllable_accented, multisyllable_not_accented};
-pub use duplicated_word::duplicated_word;
-pub use final_n::{add_final_n, remove_final_n};
-pub use forbidden_accent::{forbidden_accent, forbidden_double_accent};
-pub use missing_accent_capital::missing_accent_capital;
-pub use missing_double_accents::missing_double_accents;
-pub use mixed_scripts::mixed_scripts;
-pub use punctuation::punctuation;
+pub use accents::{
+    MonosyllableAccented, MultisyllableNotAccented, monosyllable_accented,
+    multisyllable_not_accented,
+};
+pub use duplicated_word::{DuplicatedWord, duplicated_word};
+pub use final_n::{AddFinalN, RemoveFinalN, add_final_n, remove_final_n};
+pub use forbidden_accent::{
+    ForbiddenAccent, ForbiddenDoubleAccent, forbidden_accent, forbidden_double_accent,
+};
+pub use missing_accent_capital::{MissingAccentCapital, missing_accent_capital};
+pub use missing_double_accents::{
+    MissingDoubleAccents, MissingDoubleAccentsNumeral, missing_double_accents,
+    missing_double_accents_numeral,
+};
+pub use mixed_scripts::{MixedScripts, mixed_scripts};
+pub use punctuation::{Punctuation, punctuation};
 
-pub use ambiguous_char::ambiguous_char;
-pub use forbidden_char::forbidden_char;
-pub use outdated_spelling::outdated_spelling;
+pub use ambiguous_char::{AmbiguousChar, ambiguous_char};
+pub use forbidden_char::{ForbiddenChar, forbidden_char};
+pub use outdated_spelling::{OutdatedSpelling, outdated_spelling};
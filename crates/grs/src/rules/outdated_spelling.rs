@@ -1,6 +1,6 @@
-use crate::diagnostic::{Diagnostic, Fix};
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
 use crate::range::TextRange;
-use crate::registry::Rule;
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use aho_corasick::AhoCorasick;
 use std::sync::OnceLock;
 
@@ -60,15 +60,45 @@ pub fn outdated_spelling(text: &str, diagnostics: &mut Vec<Diagnostic>) {
             diagnostics.push(Diagnostic {
                 kind: Rule::OutdatedSpelling,
                 range,
-                fix: Some(Fix {
-                    replacement: destination.to_string(),
-                    range,
-                }),
+                suggestions: vec![Suggestion::preferred(
+                    Message::new(
+                        format!("αντικατάσταση με «{destination}»"),
+                        format!("replace with «{destination}»"),
+                    ),
+                    Fix::edit(Edit::diff(range.start(), target, destination)),
+                )],
+                severity: Rule::OutdatedSpelling.severity(),
+                message: Message::new(
+                    format!("παρωχημένη ορθογραφία: «{target}» γράφεται πλέον «{destination}»"),
+                    format!("outdated spelling: «{target}» is now written «{destination}»"),
+                ),
             });
         }
     }
 }
 
+pub struct OutdatedSpelling;
+
+impl LintRule for OutdatedSpelling {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "OS",
+            name: "OutdatedSpelling",
+            phase: Phase::Raw,
+            group: Group::Typography,
+            fixable: true,
+            default: true,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Outdated spellings, ex. κρεββάτι.",
+        }
+    }
+
+    fn check_raw(&self, text: &str, diagnostics: &mut Vec<Diagnostic>) {
+        outdated_spelling(text, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,8 +6,8 @@
 //
 // ! It depends highly on our tokenization logic.
 
-use crate::diagnostic::{Diagnostic, Fix};
-use crate::registry::Rule;
+use crate::diagnostic::{Diagnostic, Edit, Fix, Message, Severity, Suggestion};
+use crate::registry::{Group, LintRule, Phase, Rule, RuleMeta};
 use crate::tokenizer::{Doc, Token};
 use grac::constants::APOSTROPHES;
 
@@ -35,11 +35,41 @@ pub fn punctuation(token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>)
         diagnostics.push(Diagnostic {
             kind: Rule::Punctuation,
             range,
-            fix: Some(Fix { replacement, range }),
+            suggestions: vec![Suggestion::preferred(
+                Message::new("προσθήκη κενού".to_string(), "add a space".to_string()),
+                Fix::edit(Edit::diff(range.start(), token.text(), &replacement)),
+            )],
+            severity: Rule::Punctuation.severity(),
+            message: Message::new(
+                format!("λείπει κενό μετά την απόστροφο στο «{}»", token.text()),
+                format!("missing space after the apostrophe in «{}»", token.text()),
+            ),
         });
     }
 }
 
+pub struct Punctuation;
+
+impl LintRule for Punctuation {
+    fn meta(&self) -> &'static RuleMeta {
+        &RuleMeta {
+            code: "P",
+            name: "Punctuation",
+            phase: Phase::Token,
+            group: Group::Typography,
+            fixable: true,
+            default: false,
+            preview: false,
+            severity: Severity::Warning,
+            docs: "Missing space after an elided word, ex. μ'αυτό.",
+        }
+    }
+
+    fn check_token(&self, token: &Token, doc: &Doc, diagnostics: &mut Vec<Diagnostic>) {
+        punctuation(token, doc, diagnostics);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
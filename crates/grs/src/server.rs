@@ -0,0 +1,896 @@
+//! Language server (`grs server`), speaking LSP over stdio.
+//!
+//! Diagnostics are published on open and change, and fixes are offered as code
+//! actions: one per diagnostic, one per rule ("fix all MDA") and one for the
+//! whole document, also available as `source.fixAll`.
+//!
+//! Rules come from the project configuration, overridden by the client settings
+//! sent on initialization and on `workspace/didChangeConfiguration`:
+//!
+//! ```json
+//! { "grs": { "select": ["ALL"], "ignore": ["DW"], "error": ["FC"] } }
+//! ```
+//!
+//! The project configuration is cached per directory, if the client can watch
+//! the configuration files for changes. Invalid messages are logged, and answered
+//! with an error if they are requests, without stopping the server.
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use itertools::Itertools;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument,
+    DidOpenTextDocument, LogMessage, Notification as _, PublishDiagnostics, ShowMessage,
+};
+use lsp_types::request::{CodeActionRequest, RegisterCapability, Request as _};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, FileSystemWatcher, GlobPattern, InitializeParams, LogMessageParams,
+    MessageType, NumberOrString, Position, PublishDiagnosticsParams, Range, Registration,
+    RegistrationParams, ServerCapabilities, ShowMessageParams, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+};
+
+use grs::cli::RuleSelector;
+use grs::diagnostic::{Diagnostic, Edit, Lang, ReportOptions, Severity};
+use grs::formats::{self, FormatOptions};
+use grs::linter::{self, Config, RuleSet};
+use grs::range::TextRange;
+use grs::registry::{Rule, rule_to_code};
+use grs::settings::{CONFIG_FILES, Settings};
+
+use crate::languagetool::percent_decode;
+
+const SOURCE: &str = "grs";
+
+/// Conversion between byte offsets and LSP positions (UTF-16 based).
+struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let character = self.text[self.line_starts[line]..offset]
+            .encode_utf16()
+            .count();
+        Position::new(line as u32, character as u32)
+    }
+
+    fn offset(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut units = 0;
+        for (idx, c) in self.text[start..].char_indices() {
+            if units >= position.character as usize || c == '\n' {
+                return start + idx;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    fn range(&self, range: TextRange) -> Range {
+        Range::new(self.position(range.start()), self.position(range.end()))
+    }
+}
+
+struct Document {
+    text: String,
+    version: i32,
+    path: Option<PathBuf>,
+}
+
+impl Document {
+    fn format(&self) -> Option<Box<dyn formats::Format>> {
+        formats::from_path(self.path.as_deref()?, &FormatOptions::default())
+    }
+
+    fn check(&self, rules: &RuleSet, report: &ReportOptions) -> Result<Vec<Diagnostic>, String> {
+        let mut diagnostics = match self.format() {
+            Some(format) => formats::check_with(format.as_ref(), &self.text, rules)?,
+            None => rules.check(&self.text),
+        };
+        report.severities.apply(&mut diagnostics);
+        Ok(diagnostics)
+    }
+
+    fn fix(&self, config: Config) -> Result<String, String> {
+        match self.format() {
+            Some(format) => Ok(formats::fix(format.as_ref(), &self.text, config)?.text),
+            None => Ok(linter::fix(&self.text, config).text),
+        }
+    }
+}
+
+/// Path of a `file://` URI.
+fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    let path = percent_decode(uri.as_str().strip_prefix("file://")?)?;
+    // Windows drive letters. Ex. file:///C:/dir
+    let path = match path.strip_prefix('/') {
+        Some(stripped) if stripped.get(1..2) == Some(":") => stripped.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Parse client settings, either at the top level or under a `grs` key.
+fn parse_client_settings(value: &serde_json::Value) -> Result<Settings, String> {
+    let value = value.get("grs").unwrap_or(value);
+    let selectors = |key: &str| -> Result<Option<Vec<RuleSelector>>, String> {
+        let Some(codes) = value.get(key).filter(|codes| !codes.is_null()) else {
+            return Ok(None);
+        };
+        let invalid = || format!("`{key}` must be a list of rule codes");
+        codes
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|code| code.as_str().ok_or_else(invalid)?.parse())
+            .collect::<Result<_, _>>()
+            .map(Some)
+    };
+    let mut settings = Settings {
+        select: selectors("select")?,
+        ignore: selectors("ignore")?,
+        preview: value.get("preview").and_then(serde_json::Value::as_bool),
+        severity: Vec::new(),
+        lang: value
+            .get("lang")
+            .and_then(serde_json::Value::as_str)
+            .map(str::parse)
+            .transpose()?,
+    };
+    for (key, severity) in [
+        ("info", Severity::Info),
+        ("warn", Severity::Warning),
+        ("error", Severity::Error),
+    ] {
+        if let Some(selectors) = selectors(key)? {
+            settings.set_severity(selectors, severity);
+        }
+    }
+    Ok(settings)
+}
+
+fn to_lsp_diagnostic(
+    index: &LineIndex,
+    diagnostic: &Diagnostic,
+    lang: Lang,
+) -> lsp_types::Diagnostic {
+    lsp_types::Diagnostic {
+        range: index.range(diagnostic.range),
+        severity: Some(match diagnostic.severity {
+            Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
+            Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+            Severity::Info => lsp_types::DiagnosticSeverity::INFORMATION,
+        }),
+        code: Some(NumberOrString::String(rule_to_code(diagnostic.kind))),
+        source: Some(SOURCE.to_string()),
+        message: diagnostic.message.get(lang).to_string(),
+        ..Default::default()
+    }
+}
+
+/// The smallest edit turning `old` into `new`.
+fn minimal_edit(index: &LineIndex, old: &str, new: &str) -> TextEdit {
+    let edit = Edit::diff(0, old, new);
+    TextEdit::new(index.range(edit.range), edit.content)
+}
+
+fn workspace_edit(uri: &Uri, edits: Vec<TextEdit>) -> WorkspaceEdit {
+    WorkspaceEdit {
+        changes: Some(HashMap::from([(uri.clone(), edits)])),
+        ..Default::default()
+    }
+}
+
+/// Code actions for the diagnostics of `document` that intersect `range`.
+fn code_actions(
+    uri: &Uri,
+    document: &Document,
+    config: Config,
+    rules: &RuleSet,
+    report: &ReportOptions,
+    range: Range,
+    only: Option<&[CodeActionKind]>,
+) -> Result<Vec<CodeActionOrCommand>, String> {
+    let wanted = |kind: &CodeActionKind| {
+        only.is_none_or(|only| {
+            only.iter()
+                .any(|requested| kind.as_str().starts_with(requested.as_str()))
+        })
+    };
+    let index = LineIndex::new(&document.text);
+    let start = index.offset(range.start);
+    let end = index.offset(range.end);
+
+    let diagnostics = document.check(rules, report)?;
+    let mut actions = Vec::new();
+
+    if wanted(&CodeActionKind::QUICKFIX) {
+        let in_range: Vec<_> = diagnostics
+            .iter()
+            .filter(|diagnostic| !diagnostic.suggestions.is_empty())
+            .filter(|diagnostic| diagnostic.range.start() <= end && start <= diagnostic.range.end())
+            .collect();
+
+        for diagnostic in &in_range {
+            let code = rule_to_code(diagnostic.kind);
+            for suggestion in &diagnostic.suggestions {
+                let title = format!("Fix {code}: {}", suggestion.label.get(report.lang));
+                let edits = suggestion
+                    .fix
+                    .edits()
+                    .iter()
+                    .map(|edit| TextEdit::new(index.range(edit.range), edit.content.clone()))
+                    .collect();
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![to_lsp_diagnostic(&index, diagnostic, report.lang)]),
+                    edit: Some(workspace_edit(uri, edits)),
+                    is_preferred: Some(suggestion.preferred),
+                    ..Default::default()
+                }));
+            }
+        }
+
+        let fixable = in_range
+            .iter()
+            .filter(|diagnostic| diagnostic.fix().is_some());
+        for rule in fixable.map(|diagnostic| diagnostic.kind).unique() {
+            let fixed = document.fix(&[rule])?;
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Fix all {} problems", rule_to_code(rule)),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(workspace_edit(
+                    uri,
+                    vec![minimal_edit(&index, &document.text, &fixed)],
+                )),
+                ..Default::default()
+            }));
+        }
+    }
+
+    let fixable = diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.fix().is_some());
+    if fixable && wanted(&CodeActionKind::SOURCE_FIX_ALL) {
+        let fixed = document.fix(config)?;
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Fix all grs problems".to_string(),
+            kind: Some(CodeActionKind::SOURCE_FIX_ALL),
+            edit: Some(workspace_edit(
+                uri,
+                vec![minimal_edit(&index, &document.text, &fixed)],
+            )),
+            ..Default::default()
+        }));
+    }
+
+    Ok(actions)
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Uri, Document>,
+    /// Settings sent by the client, which take precedence over the project ones.
+    client_settings: Settings,
+    /// Project root, used for documents without a path.
+    root: Option<PathBuf>,
+    /// Whether the client notifies changes of the configuration files, so that
+    /// the project settings can be cached.
+    watching: bool,
+    /// Project settings by directory.
+    project_settings: RefCell<HashMap<PathBuf, Settings>>,
+    /// Warnings about the settings already logged, to log each one once.
+    warned: RefCell<HashSet<String>>,
+    /// The rules of each config, dispatched once instead of at every change.
+    rule_sets: RefCell<HashMap<Vec<Rule>, Rc<RuleSet>>>,
+}
+
+impl Server {
+    fn send(&self, message: impl Into<Message>) -> Result<(), String> {
+        self.connection
+            .sender
+            .send(message.into())
+            .map_err(|err| err.to_string())
+    }
+
+    fn show_error(&self, message: String) -> Result<(), String> {
+        let params = ShowMessageParams {
+            typ: MessageType::ERROR,
+            message: format!("grs: {message}"),
+        };
+        self.send(Notification::new(ShowMessage::METHOD.to_string(), params))
+    }
+
+    fn log(&self, typ: MessageType, message: String) -> Result<(), String> {
+        let params = LogMessageParams {
+            typ,
+            message: format!("grs: {message}"),
+        };
+        self.send(Notification::new(LogMessage::METHOD.to_string(), params))
+    }
+
+    fn log_error(&self, message: String) -> Result<(), String> {
+        self.log(MessageType::ERROR, message)
+    }
+
+    fn log_warnings(&self, settings: &Settings) {
+        for warning in settings.warnings() {
+            if self.warned.borrow_mut().insert(warning.clone()) {
+                let _ = self.log(MessageType::WARNING, warning);
+            }
+        }
+    }
+
+    fn discover(&self, dir: &Path) -> Settings {
+        Settings::discover(dir).unwrap_or_else(|err| {
+            let _ = self.show_error(err);
+            Settings::default()
+        })
+    }
+
+    fn settings(&self, document: &Document) -> Settings {
+        let dir = document
+            .path
+            .as_deref()
+            .and_then(Path::parent)
+            .or(self.root.as_deref());
+        let project = match dir {
+            Some(dir) if self.watching => self
+                .project_settings
+                .borrow_mut()
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| self.discover(dir))
+                .clone(),
+            Some(dir) => self.discover(dir),
+            None => Settings::default(),
+        };
+        let settings = self.client_settings.clone().or(project);
+        self.log_warnings(&settings);
+        settings
+    }
+
+    fn rule_set(&self, config: Vec<Rule>) -> Rc<RuleSet> {
+        self.rule_sets
+            .borrow_mut()
+            .entry(config)
+            .or_insert_with_key(|config| Rc::new(RuleSet::new(config)))
+            .clone()
+    }
+
+    fn publish(&self, uri: &Uri) -> Result<(), String> {
+        let Some(document) = self.documents.get(uri) else {
+            return Ok(());
+        };
+        let settings = self.settings(document);
+        let index = LineIndex::new(&document.text);
+        let report = settings.report_options();
+        let rules = self.rule_set(settings.rules());
+        let diagnostics = match document.check(&rules, &report) {
+            Ok(diagnostics) => diagnostics
+                .iter()
+                .map(|diagnostic| to_lsp_diagnostic(&index, diagnostic, report.lang))
+                .collect(),
+            Err(err) => vec![lsp_types::Diagnostic {
+                severity: Some(lsp_types::DiagnosticSeverity::ERROR),
+                source: Some(SOURCE.to_string()),
+                message: format!("Failed to parse: {err}"),
+                ..Default::default()
+            }],
+        };
+        let params =
+            PublishDiagnosticsParams::new(uri.clone(), diagnostics, Some(document.version));
+        self.send(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        ))
+    }
+
+    fn handle_request(&self, request: Request) -> Result<(), String> {
+        let id = request.id.clone();
+        let method = request.method.clone();
+        let response = match self.respond(request) {
+            Ok(response) => response,
+            Err((code, err)) => {
+                let message = format!("{method}: {err}");
+                if !matches!(code, ErrorCode::MethodNotFound) {
+                    self.log_error(message.clone())?;
+                }
+                Response::new_err(id, code as i32, message)
+            }
+        };
+        self.send(response)
+    }
+
+    fn respond(&self, request: Request) -> Result<Response, (ErrorCode, String)> {
+        if request.method != CodeActionRequest::METHOD {
+            return Err((ErrorCode::MethodNotFound, "unsupported request".to_string()));
+        }
+        let params: CodeActionParams = serde_json::from_value(request.params)
+            .map_err(|err| (ErrorCode::InvalidParams, err.to_string()))?;
+        let uri = &params.text_document.uri;
+        let actions = match self.documents.get(uri) {
+            Some(document) => {
+                let settings = self.settings(document);
+                let config = settings.rules();
+                code_actions(
+                    uri,
+                    document,
+                    &config,
+                    &self.rule_set(config.clone()),
+                    &settings.report_options(),
+                    params.range,
+                    params.context.only.as_deref(),
+                )
+                .map_err(|err| (ErrorCode::RequestFailed, err))?
+            }
+            None => Vec::new(),
+        };
+        Ok(Response::new_ok(request.id, actions))
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<(), String> {
+        let params = notification.params;
+        let parse_error = |err: serde_json::Error| err.to_string();
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(params).map_err(parse_error)?;
+                let document = params.text_document;
+                let path = uri_to_path(&document.uri);
+                self.documents.insert(
+                    document.uri.clone(),
+                    Document {
+                        text: document.text,
+                        version: document.version,
+                        path,
+                    },
+                );
+                self.publish(&document.uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(params).map_err(parse_error)?;
+                let uri = params.text_document.uri;
+                if let Some(document) = self.documents.get_mut(&uri)
+                    && let Some(change) = params.content_changes.into_iter().last()
+                {
+                    document.text = change.text;
+                    document.version = params.text_document.version;
+                }
+                self.publish(&uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(params).map_err(parse_error)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                let params = PublishDiagnosticsParams::new(uri, Vec::new(), None);
+                self.send(Notification::new(
+                    PublishDiagnostics::METHOD.to_string(),
+                    params,
+                ))
+            }
+            DidChangeConfiguration::METHOD => {
+                let params: DidChangeConfigurationParams =
+                    serde_json::from_value(params).map_err(parse_error)?;
+                match parse_client_settings(&params.settings) {
+                    Ok(settings) => self.client_settings = settings,
+                    Err(err) => return self.show_error(err),
+                }
+                let uris: Vec<_> = self.documents.keys().cloned().collect();
+                uris.iter().try_for_each(|uri| self.publish(uri))
+            }
+            // Only the configuration files are watched.
+            DidChangeWatchedFiles::METHOD => {
+                self.project_settings.borrow_mut().clear();
+                let uris: Vec<_> = self.documents.keys().cloned().collect();
+                uris.iter().try_for_each(|uri| self.publish(uri))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn main_loop(&mut self) -> Result<(), String> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self
+                        .connection
+                        .handle_shutdown(&request)
+                        .map_err(|err| err.to_string())?
+                    {
+                        return Ok(());
+                    }
+                    self.handle_request(request)?;
+                }
+                Message::Notification(notification) => {
+                    // An invalid notification is logged: the connection only
+                    // fails if the log can not be sent either.
+                    let method = notification.method.clone();
+                    if let Err(err) = self.handle_notification(notification) {
+                        self.log_error(format!("{method}: {err}"))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run the language server until the client shuts it down.
+///
+/// # Errors
+///
+/// Returns an error message if the connection with the client fails.
+pub fn run() -> Result<(), String> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![
+                CodeActionKind::QUICKFIX,
+                CodeActionKind::SOURCE_FIX_ALL,
+            ]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    let capabilities = serde_json::to_value(capabilities).map_err(|err| err.to_string())?;
+    let params = connection
+        .initialize(capabilities)
+        .map_err(|err| err.to_string())?;
+    let params: InitializeParams = serde_json::from_value(params).map_err(|err| err.to_string())?;
+
+    #[allow(deprecated)]
+    let root = params
+        .workspace_folders
+        .as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| &folder.uri)
+        .or(params.root_uri.as_ref())
+        .and_then(uri_to_path);
+    let client_settings = params
+        .initialization_options
+        .as_ref()
+        .map(parse_client_settings)
+        .transpose()?
+        .unwrap_or_default();
+
+    let watching = params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+        .and_then(|capabilities| capabilities.dynamic_registration)
+        .unwrap_or(false);
+    if watching {
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: CONFIG_FILES
+                .iter()
+                .map(|name| FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(format!("**/{name}")),
+                    kind: None,
+                })
+                .collect(),
+        };
+        let params = RegistrationParams {
+            registrations: vec![Registration {
+                id: "grs/watch-configuration".to_string(),
+                method: DidChangeWatchedFiles::METHOD.to_string(),
+                register_options: Some(
+                    serde_json::to_value(options).map_err(|err| err.to_string())?,
+                ),
+            }],
+        };
+        let request = Request::new(
+            RequestId::from("grs/watch-configuration".to_string()),
+            RegisterCapability::METHOD.to_string(),
+            params,
+        );
+        connection
+            .sender
+            .send(request.into())
+            .map_err(|err| err.to_string())?;
+    }
+
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+        client_settings,
+        root,
+        watching,
+        project_settings: RefCell::new(HashMap::new()),
+        warned: RefCell::new(HashSet::new()),
+        rule_sets: RefCell::new(HashMap::new()),
+    };
+    server.main_loop()?;
+    drop(server);
+    io_threads.join().map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_line_index() {
+        let text = "αβ\n😀 γ";
+        let index = LineIndex::new(text);
+        let offset = text.find('γ').unwrap();
+        assert_eq!(index.position(offset), Position::new(1, 3));
+        assert_eq!(index.offset(Position::new(1, 3)), offset);
+        assert_eq!(index.offset(Position::new(0, 10)), "αβ".len());
+        assert_eq!(index.offset(Position::new(5, 0)), text.len());
+    }
+
+    #[test]
+    fn test_uri_to_path() {
+        let uri = Uri::from_str("file:///home/user/%CE%B1.txt").unwrap();
+        assert_eq!(uri_to_path(&uri), Some(PathBuf::from("/home/user/α.txt")));
+        let uri = Uri::from_str("untitled:Untitled-1").unwrap();
+        assert_eq!(uri_to_path(&uri), None);
+    }
+
+    #[test]
+    fn test_client_settings() {
+        let value = serde_json::json!({ "grs": { "select": ["ALL"], "ignore": ["DW"], "info": ["FC"], "lang": "el" } });
+        let settings = parse_client_settings(&value).unwrap();
+        assert_eq!(settings.select, Some(vec![RuleSelector::All]));
+        assert_eq!(settings.lang(), Lang::El);
+        assert_eq!(
+            settings.severities().get(Rule::ForbiddenChar),
+            Severity::Info
+        );
+        assert!(parse_client_settings(&serde_json::json!({ "select": ["XYZ"] })).is_err());
+        assert_eq!(
+            parse_client_settings(&serde_json::json!({})).unwrap(),
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn test_code_actions() {
+        let uri = Uri::from_str("untitled:Untitled-1").unwrap();
+        let document = Document {
+            text: "Aλλά στην δόξα".to_string(),
+            version: 1,
+            path: None,
+        };
+        let config = [Rule::MixedScripts, Rule::RemoveFinalN];
+        let report = ReportOptions::default();
+        let everywhere = Range::new(Position::new(0, 0), Position::new(1, 0));
+
+        let rules = RuleSet::new(&config);
+        let actions =
+            code_actions(&uri, &document, &config, &rules, &report, everywhere, None).unwrap();
+        let titles: Vec<_> = actions
+            .iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => action.title.as_str(),
+                CodeActionOrCommand::Command(command) => command.title.as_str(),
+            })
+            .collect();
+        assert!(titles.contains(&"Fix RFN: remove the final -ν"));
+        assert!(titles.contains(&"Fix all MS problems"));
+        assert!(titles.contains(&"Fix all grs problems"));
+
+        let only = [CodeActionKind::SOURCE_FIX_ALL];
+        let actions = code_actions(
+            &uri,
+            &document,
+            &config,
+            &rules,
+            &report,
+            everywhere,
+            Some(&only),
+        )
+        .unwrap();
+        assert_eq!(actions.len(), 1);
+    }
+
+    #[test]
+    fn test_alternative_code_actions() {
+        let uri = Uri::from_str("untitled:Untitled-1").unwrap();
+        let document = Document {
+            text: "λάθος λάθος".to_string(),
+            version: 1,
+            path: None,
+        };
+        let everywhere = Range::new(Position::new(0, 0), Position::new(1, 0));
+        let report = ReportOptions::default();
+        let actions = code_actions(
+            &uri,
+            &document,
+            &[Rule::DuplicatedWord],
+            &RuleSet::new(&[Rule::DuplicatedWord]),
+            &report,
+            everywhere,
+            None,
+        )
+        .unwrap();
+        let titles: Vec<_> = actions
+            .iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => {
+                    assert_eq!(action.is_preferred, Some(false));
+                    action.title.as_str()
+                }
+                CodeActionOrCommand::Command(command) => command.title.as_str(),
+            })
+            .collect();
+        // Both suggestions, but nothing to fix all at once.
+        assert_eq!(
+            titles,
+            ["Fix DW: delete the repetition", "Fix DW: add a comma"]
+        );
+    }
+
+    #[test]
+    fn test_minimal_edit() {
+        let old = "Aλλά στην δόξα";
+        let new = "Αλλά στη δόξα";
+        let index = LineIndex::new(old);
+        let edit = minimal_edit(&index, old, new);
+        assert_eq!(
+            edit.range,
+            Range::new(Position::new(0, 0), Position::new(0, 9))
+        );
+        assert_eq!(edit.new_text, "Αλλά στη");
+    }
+
+    fn server(connection: Connection, watching: bool) -> Server {
+        Server {
+            connection,
+            documents: HashMap::new(),
+            client_settings: Settings::default(),
+            root: None,
+            watching,
+            project_settings: RefCell::new(HashMap::new()),
+            warned: RefCell::new(HashSet::new()),
+            rule_sets: RefCell::new(HashMap::new()),
+        }
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        let (connection, client) = Connection::memory();
+        let invalid = serde_json::json!({ "textDocument": 1 });
+        let messages: [Message; 3] = [
+            Notification::new(DidOpenTextDocument::METHOD.to_string(), invalid.clone()).into(),
+            Request::new(
+                RequestId::from(1),
+                CodeActionRequest::METHOD.to_string(),
+                invalid,
+            )
+            .into(),
+            Notification::new(
+                DidOpenTextDocument::METHOD.to_string(),
+                serde_json::json!({ "textDocument": {
+                    "uri": "untitled:Untitled-1", "languageId": "plaintext", "version": 1, "text": "Στην δόξα"
+                } }),
+            )
+            .into(),
+        ];
+        for message in messages {
+            client.sender.send(message).unwrap();
+        }
+        drop(client.sender);
+
+        // The server keeps running until the client disconnects.
+        server(connection, false).main_loop().unwrap();
+        let methods: Vec<_> = client
+            .receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Notification(notification) => notification.method,
+                Message::Response(response) => {
+                    assert_eq!(
+                        response.error.unwrap().code,
+                        ErrorCode::InvalidParams as i32
+                    );
+                    "response".to_string()
+                }
+                Message::Request(request) => request.method,
+            })
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                LogMessage::METHOD,
+                LogMessage::METHOD,
+                "response",
+                PublishDiagnostics::METHOD
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rule_sets() {
+        let (connection, _client) = Connection::memory();
+        let server = server(connection, false);
+        let rules = server.rule_set(vec![Rule::RemoveFinalN]);
+        assert!(Rc::ptr_eq(
+            &rules,
+            &server.rule_set(vec![Rule::RemoveFinalN])
+        ));
+        assert!(!Rc::ptr_eq(
+            &rules,
+            &server.rule_set(vec![Rule::MixedScripts])
+        ));
+    }
+
+    #[test]
+    fn test_settings_warnings() {
+        let (connection, client) = Connection::memory();
+        let mut server = server(connection, false);
+        server.client_settings = Settings::from_toml("select = [\"MDAN\"]").unwrap();
+        let document = Document {
+            text: String::new(),
+            version: 1,
+            path: None,
+        };
+        server.settings(&document);
+        server.settings(&document);
+        let messages: Vec<_> = client
+            .receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                Message::Notification(notification) => {
+                    serde_json::from_value::<LogMessageParams>(notification.params).ok()
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].typ, MessageType::WARNING);
+        assert!(messages[0].message.contains("MDAN is in preview"));
+    }
+
+    #[test]
+    fn test_settings_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("grs.toml");
+        std::fs::write(&config, "select = [\"RFN\"]").unwrap();
+        let document = Document {
+            text: String::new(),
+            version: 1,
+            path: Some(dir.path().join("κείμενο.txt")),
+        };
+        let (connection, _client) = Connection::memory();
+        let mut server = server(connection, true);
+        assert_eq!(server.settings(&document).rules(), [Rule::RemoveFinalN]);
+
+        // Only read again once the client notifies the change.
+        std::fs::write(&config, "select = [\"MS\"]").unwrap();
+        assert_eq!(server.settings(&document).rules(), [Rule::RemoveFinalN]);
+        let changed = Notification::new(
+            DidChangeWatchedFiles::METHOD.to_string(),
+            serde_json::json!({ "changes": [] }),
+        );
+        server.handle_notification(changed).unwrap();
+        assert_eq!(server.settings(&document).rules(), [Rule::MixedScripts]);
+    }
+}
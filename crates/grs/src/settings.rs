@@ -0,0 +1,366 @@
+//! Project configuration.
+//!
+//! Read from the first `grs.toml` (or `.grs.toml`) found in the directory of a
+//! file or in one of its ancestors:
+//!
+//! ```toml
+//! select = ["ALL"]
+//! ignore = ["DW", "typography"]
+//! preview = true
+//! warn = ["DW"]
+//! error = ["FC"]
+//! lang = "el"
+//! ```
+use itertools::Itertools;
+use std::path::{Path, PathBuf};
+
+use crate::cli::RuleSelector;
+use crate::diagnostic::{Lang, ReportOptions, Severities, Severity};
+use crate::registry::{self, Rule};
+
+pub const CONFIG_FILES: [&str; 2] = ["grs.toml", ".grs.toml"];
+
+/// Rules used when nothing is selected.
+pub fn default_rules() -> Vec<Rule> {
+    Rule::iter().filter(Rule::is_default).collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    pub select: Option<Vec<RuleSelector>>,
+    pub ignore: Option<Vec<RuleSelector>>,
+    /// Whether to use the rules in preview.
+    pub preview: Option<bool>,
+    /// Severities of the rules of the selectors, overriding their default.
+    /// Later entries take precedence.
+    pub severity: Vec<(RuleSelector, Severity)>,
+    /// Language of the messages of diagnostics.
+    pub lang: Option<Lang>,
+}
+
+fn parse_selectors(key: &str, value: &toml::Value) -> Result<Vec<RuleSelector>, String> {
+    let invalid = || format!("`{key}` must be a list of rule codes");
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|code| code.as_str().ok_or_else(invalid)?.parse())
+        .collect()
+}
+
+impl Settings {
+    /// Parse the content of a configuration file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message on invalid TOML, unknown keys or unknown rules.
+    pub fn from_toml(source: &str) -> Result<Self, String> {
+        let table: toml::Table = source
+            .parse()
+            .map_err(|err: toml::de::Error| err.message().to_string())?;
+        let mut settings = Self::default();
+        for (key, value) in &table {
+            match key.as_str() {
+                "select" => settings.select = Some(parse_selectors(key, value)?),
+                "ignore" => settings.ignore = Some(parse_selectors(key, value)?),
+                "info" | "warn" | "error" => {
+                    let severity = match key.as_str() {
+                        "info" => Severity::Info,
+                        "warn" => Severity::Warning,
+                        _ => Severity::Error,
+                    };
+                    let selectors = parse_selectors(key, value)?;
+                    settings.set_severity(selectors, severity);
+                }
+                "preview" => {
+                    let preview = value.as_bool();
+                    settings.preview =
+                        Some(preview.ok_or_else(|| format!("`{key}` must be a boolean"))?);
+                }
+                "lang" => {
+                    let code = value.as_str();
+                    settings.lang = Some(
+                        code.ok_or_else(|| format!("`{key}` must be a string"))?
+                            .parse()?,
+                    );
+                }
+                _ => return Err(format!("unknown key `{key}`")),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error message if the file can not be read or parsed.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_toml(&source).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    /// Find the configuration file that applies to `path`, a file or a directory.
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .flat_map(|dir| CONFIG_FILES.iter().map(move |name| dir.join(name)))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// The settings that apply to `path`, or the default ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if the configuration file is invalid.
+    pub fn discover(path: &Path) -> Result<Self, String> {
+        Self::find(path).map_or_else(|| Ok(Self::default()), |config| Self::from_file(&config))
+    }
+
+    /// Give `severity` to the rules of `selectors`.
+    pub fn set_severity(&mut self, selectors: Vec<RuleSelector>, severity: Severity) {
+        self.severity
+            .extend(selectors.into_iter().map(|selector| (selector, severity)));
+    }
+
+    /// Use the values of `fallback` for the keys not set here.
+    ///
+    /// Severities are merged, those set here taking precedence.
+    #[must_use]
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            select: self.select.or(fallback.select),
+            ignore: self.ignore.or(fallback.ignore),
+            preview: self.preview.or(fallback.preview),
+            severity: [fallback.severity, self.severity].concat(),
+            lang: self.lang.or(fallback.lang),
+        }
+    }
+
+    pub fn preview(&self) -> bool {
+        self.preview.unwrap_or(false)
+    }
+
+    /// The severities of the rules, where they differ from the default.
+    pub fn severities(&self) -> Severities {
+        let mut severities = Severities::default();
+        for (selector, severity) in &self.severity {
+            for rule in selector.rules() {
+                severities.set(rule, *severity);
+            }
+        }
+        severities
+    }
+
+    pub fn lang(&self) -> Lang {
+        self.lang.unwrap_or_default()
+    }
+
+    /// How to report diagnostics: their severities and the language of their
+    /// message.
+    pub fn report_options(&self) -> ReportOptions {
+        ReportOptions {
+            severities: self.severities(),
+            lang: self.lang(),
+        }
+    }
+
+    /// The rules to check.
+    ///
+    /// Rules in preview are only used if preview is on, even when selected
+    /// explicitly (see [`Settings::warnings`]).
+    pub fn rules(&self) -> Vec<Rule> {
+        let mut rules: Vec<Rule> = match &self.select {
+            None => default_rules(),
+            Some(selection) => selection
+                .iter()
+                .flat_map(RuleSelector::rules)
+                .unique()
+                .collect(),
+        };
+        // Does not crash if rules to ignore were not in config.
+        if let Some(selection) = &self.ignore {
+            rules.retain(|rule| {
+                !selection
+                    .iter()
+                    .any(|selector| selector.rules().contains(rule))
+            });
+        }
+        if !self.preview() {
+            rules.retain(|rule| !rule.is_preview());
+        }
+        rules
+    }
+
+    /// Warnings for the user, about deprecated rule codes and about rules in
+    /// preview selected explicitly without preview on. They are left to the
+    /// caller to show.
+    pub fn warnings(&self) -> Vec<String> {
+        let ignored = |rule: &Rule| {
+            self.ignore
+                .iter()
+                .flatten()
+                .any(|selector| selector.rules().contains(rule))
+        };
+        let selectors = self
+            .select
+            .iter()
+            .chain(&self.ignore)
+            .flatten()
+            .chain(self.severity.iter().map(|(selector, _)| selector));
+        let deprecations = selectors.filter_map(|selector| match selector {
+            RuleSelector::Redirect(old, rule) => Some(registry::deprecation(old, *rule)),
+            _ => None,
+        });
+        let previews = self
+            .select
+            .iter()
+            .flatten()
+            .filter(|_| !self.preview())
+            .filter_map(|selector| match selector {
+                RuleSelector::Selection(rule) | RuleSelector::Redirect(_, rule)
+                    if rule.is_preview() && !ignored(rule) =>
+                {
+                    Some(format!("rule {rule} is in preview and requires --preview"))
+                }
+                _ => None,
+            });
+        deprecations.chain(previews).unique().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let settings = Settings::from_toml("select = [\"ALL\"]\nignore = [\"DW\"]").unwrap();
+        let rules = settings.rules();
+        let stable = Rule::iter().filter(|rule| !rule.is_preview()).count();
+        assert_eq!(rules.len(), stable - 1);
+        assert!(!rules.contains(&Rule::DuplicatedWord));
+
+        assert!(Settings::from_toml("select = [\"XYZ\"]").is_err());
+        assert!(Settings::from_toml("select = \"ALL\"").is_err());
+        assert!(Settings::from_toml("unknown = 1").is_err());
+        assert_eq!(Settings::from_toml("").unwrap().rules(), default_rules());
+        assert_eq!(
+            default_rules(),
+            [
+                Rule::MissingDoubleAccents,
+                Rule::OutdatedSpelling,
+                Rule::MonosyllableAccented,
+                Rule::MultisyllableNotAccented,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_groups_and_prefixes() {
+        let settings =
+            Settings::from_toml("select = [\"accent\", \"F\"]\nignore = [\"FA\"]").unwrap();
+        assert_eq!(
+            settings.rules(),
+            [
+                Rule::MissingDoubleAccents,
+                Rule::MissingAccentCapital,
+                Rule::MonosyllableAccented,
+                Rule::MultisyllableNotAccented,
+                Rule::ForbiddenDoubleAccent,
+                Rule::ForbiddenChar,
+            ]
+        );
+        // Codes are not prefixes of longer codes.
+        let settings = Settings::from_toml("select = [\"MA\"]").unwrap();
+        assert_eq!(settings.rules(), [Rule::MonosyllableAccented]);
+        assert!(Settings::from_toml("select = [\"accents\"]").is_err());
+    }
+
+    #[test]
+    fn test_or() {
+        let cli = Settings {
+            select: Some(vec![RuleSelector::Selection(Rule::DuplicatedWord)]),
+            ignore: None,
+            preview: None,
+            severity: Vec::new(),
+            lang: None,
+        };
+        let file = Settings::from_toml("select = [\"ALL\"]\nignore = [\"DW\"]").unwrap();
+        let settings = cli.or(file);
+        assert!(settings.rules().is_empty());
+    }
+
+    #[test]
+    fn test_severities() {
+        let file = Settings::from_toml("warn = [\"DW\", \"FC\"]\ninfo = [\"typography\"]").unwrap();
+        let severities = file.severities();
+        assert_eq!(severities.get(Rule::DuplicatedWord), Severity::Warning);
+        assert_eq!(severities.get(Rule::Punctuation), Severity::Info);
+        assert_eq!(
+            severities.get(Rule::MultisyllableNotAccented),
+            Severity::Warning
+        );
+
+        // The command line takes precedence.
+        let mut cli = Settings::default();
+        cli.set_severity(
+            vec![RuleSelector::Selection(Rule::ForbiddenChar)],
+            Severity::Error,
+        );
+        let severities = cli.or(file).severities();
+        assert_eq!(severities.get(Rule::ForbiddenChar), Severity::Error);
+        assert_eq!(severities.get(Rule::DuplicatedWord), Severity::Warning);
+        assert!(Settings::from_toml("error = [\"XYZ\"]").is_err());
+    }
+
+    #[test]
+    fn test_preview() {
+        let settings = Settings::from_toml("select = [\"ALL\"]").unwrap();
+        assert!(
+            !settings
+                .rules()
+                .contains(&Rule::MissingDoubleAccentsNumeral)
+        );
+        let settings = Settings::from_toml("select = [\"MDAN\"]").unwrap();
+        assert!(settings.rules().is_empty());
+        assert_eq!(
+            settings.warnings(),
+            ["rule MDAN is in preview and requires --preview"]
+        );
+        let settings = Settings::from_toml("select = [\"MDAN\"]\nignore = [\"MDAN\"]").unwrap();
+        assert!(settings.warnings().is_empty());
+
+        let settings = Settings::from_toml("select = [\"ALL\"]\npreview = true").unwrap();
+        assert_eq!(settings.rules().len(), Rule::iter().count());
+        assert!(settings.warnings().is_empty());
+        assert!(Settings::from_toml("preview = 1").is_err());
+    }
+
+    #[test]
+    fn test_deprecations() {
+        let redirect = RuleSelector::Redirect("RMFN", Rule::RemoveFinalN);
+        let mut settings = Settings {
+            select: Some(vec![redirect.clone()]),
+            ignore: Some(vec![redirect.clone()]),
+            ..Settings::default()
+        };
+        settings.set_severity(vec![redirect], Severity::Error);
+        assert_eq!(settings.rules(), []);
+        assert_eq!(
+            settings.warnings(),
+            ["rule code `RMFN` is deprecated, use `RFN` instead"]
+        );
+    }
+
+    #[test]
+    fn test_lang() {
+        assert_eq!(Settings::default().lang(), Lang::En);
+        let file = Settings::from_toml("lang = \"el\"").unwrap();
+        assert_eq!(file.lang(), Lang::El);
+        let cli = Settings {
+            lang: Some(Lang::En),
+            ..Settings::default()
+        };
+        assert_eq!(cli.or(file).report_options().lang, Lang::En);
+        assert!(Settings::from_toml("lang = \"fr\"").is_err());
+        assert!(Settings::from_toml("lang = 1").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! Tests of the command line interface.
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Run `grs check` on `text` with `args`, returning whether it succeeded and
+/// the text of the file afterwards.
+fn check(dir: &Path, text: &str, args: &[&str]) -> (bool, String) {
+    let path = dir.join("κείμενο.txt");
+    fs::write(&path, text).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_grs"))
+        .current_dir(dir)
+        .arg("check")
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    (output.status.success(), fs::read_to_string(&path).unwrap())
+}
+
+#[test]
+fn test_fix_exit_status() {
+    let dir = tempfile::tempdir().unwrap();
+    let args = ["--select", "FC,RFN", "--error", "FC,RFN"];
+    assert!(!check(dir.path(), "Πιςτεύω στην δόξα", &args).0);
+
+    // FC has no fix: the error is left and the check still fails.
+    let fix_args = [&args[..], &["--fix"]].concat();
+    let (success, fixed) = check(dir.path(), "Πιςτεύω στην δόξα", &fix_args);
+    assert!(!success);
+    assert_eq!(fixed, "Πιςτεύω στη δόξα");
+
+    // Every error fixed.
+    let (success, fixed) = check(dir.path(), "Πιστεύω στην δόξα", &fix_args);
+    assert!(success);
+    assert_eq!(fixed, "Πιστεύω στη δόξα");
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=grs", "-c", "user.email=grs@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_staged() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("κείμενο.txt");
+    git(dir.path(), &["init", "--quiet"]);
+    fs::write(&path, "Πρώτη.\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "--quiet", "-m", "Πρώτη"]);
+
+    // The error is on the second line of the index, but on the third one of
+    // the working tree.
+    fs::write(&path, "Πρώτη.\nΣτην δόξα.\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    fs::write(&path, "Νέα.\nΠρώτη.\nΣτην δόξα.\n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_grs"))
+            .current_dir(dir.path())
+            .args(["check", "--select", "RFN", "--error", "RFN", "--staged"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let output = run(&[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("RFN"));
+
+    // Only the working tree would be fixed.
+    assert!(!run(&["--fix"]).status.success());
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "Νέα.\nΠρώτη.\nΣτην δόξα.\n"
+    );
+}
+
+#[test]
+fn test_to_monotonic_skips_other_formats() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("κείμενο.tex");
+    let text = "\\section{Ἡ ἀρχή}\nἩ ἀρχή.\n";
+    fs::write(&path, text).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_grs"))
+        .current_dir(dir.path())
+        .arg("to-monotonic")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("converted"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), text);
+}
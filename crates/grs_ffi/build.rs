@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+/// Set to regenerate the checked-in header, `include/grs.h`.
+const WRITE_HEADER: &str = "GRS_FFI_WRITE_HEADER";
+
+fn main() {
+    let crate_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let bindings =
+        cbindgen::generate_with_config(&crate_dir, config).expect("Unable to generate bindings");
+    // The source tree is only written to on demand: it may be read-only.
+    bindings.write_to_file(out_dir.join("grs.h"));
+    if std::env::var_os(WRITE_HEADER).is_some() {
+        bindings.write_to_file(crate_dir.join("include/grs.h"));
+    }
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed={WRITE_HEADER}");
+}
@@ -0,0 +1,135 @@
+#ifndef GRS_H
+#define GRS_H
+
+/* Generated by cbindgen from crates/grs_ffi. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Maximum length of a rule code, including the terminating NUL.
+ */
+#define GRS_CODE_SIZE 8
+
+typedef enum GrsStatus {
+  GRS_STATUS_OK = 0,
+  /**
+   * A null pointer where one was not expected.
+   */
+  GRS_STATUS_NULL_POINTER,
+  GRS_STATUS_INVALID_UTF8,
+  GRS_STATUS_UNKNOWN_RULE,
+  /**
+   * A bug in grs.
+   */
+  GRS_STATUS_PANIC,
+} GrsStatus;
+
+/**
+ * Severity of a diagnostic. Only errors should fail a check.
+ */
+typedef enum GrsSeverity {
+  GRS_SEVERITY_INFO = 0,
+  GRS_SEVERITY_WARNING,
+  GRS_SEVERITY_ERROR,
+} GrsSeverity;
+
+/**
+ * A linter: the rules to check. Opaque.
+ */
+typedef struct GrsLinter GrsLinter;
+
+typedef struct GrsDiagnostic {
+  /**
+   * NUL-terminated rule code, ex. `MDA`.
+   */
+  char code[GRS_CODE_SIZE];
+  /**
+   * Byte range of the diagnostic.
+   */
+  size_t start;
+  size_t end;
+  /**
+   * Byte range replaced by the fix. Only meaningful if there is a fix.
+   */
+  size_t fix_start;
+  size_t fix_end;
+  /**
+   * NUL-terminated replacement, or null if there is no fix.
+   */
+  char *replacement;
+  enum GrsSeverity severity;
+} GrsDiagnostic;
+
+typedef struct GrsDiagnostics {
+  struct GrsDiagnostic *ptr;
+  size_t len;
+} GrsDiagnostics;
+
+/**
+ * An UTF-8 buffer, not NUL-terminated.
+ */
+typedef struct GrsString {
+  uint8_t *ptr;
+  size_t len;
+} GrsString;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The version of grs, as a static NUL-terminated string.
+ */
+const char *grs_version(void);
+
+/**
+ * A static NUL-terminated description of `status`.
+ */
+const char *grs_status_message(enum GrsStatus status);
+
+/**
+ * Create a linter for `rules`, a NUL-terminated comma-separated list of rule
+ * codes (or `ALL`). The default rules are used if `rules` is null. Rules in
+ * preview are only used with `preview`.
+ */
+enum GrsStatus grs_linter_new(const char *rules, bool preview, struct GrsLinter **out);
+
+void grs_linter_free(struct GrsLinter *linter);
+
+/**
+ * Check the UTF-8 buffer `text` of `len` bytes. On success, `out` must be
+ * released with `grs_diagnostics_free`.
+ */
+enum GrsStatus grs_check(const struct GrsLinter *linter,
+                         const uint8_t *text,
+                         size_t len,
+                         struct GrsDiagnostics *out);
+
+/**
+ * Release the diagnostics returned by `grs_check`, and reset them to empty.
+ */
+void grs_diagnostics_free(struct GrsDiagnostics *diagnostics);
+
+/**
+ * Fix the UTF-8 buffer `text` of `len` bytes. On success, `out` must be
+ * released with `grs_string_free`.
+ */
+enum GrsStatus grs_fix(const struct GrsLinter *linter,
+                       const uint8_t *text,
+                       size_t len,
+                       struct GrsString *out);
+
+/**
+ * Release a string returned by `grs_fix`, and reset it to empty.
+ */
+void grs_string_free(struct GrsString *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GRS_H */
@@ -0,0 +1,435 @@
+//! C bindings. The header, `include/grs.h`, is generated by cbindgen and
+//! checked in: regenerate it with `GRS_FFI_WRITE_HEADER=1 cargo build -p grs_ffi`.
+//!
+//! Texts are UTF-8 buffers given by pointer and length, and ranges are byte
+//! offsets into them. Everything returned by grs is released with the matching
+//! `grs_*_free` function. Functions return a [`GrsStatus`] and never unwind:
+//! panics are caught and reported as [`GrsStatus::Panic`].
+//!
+//! ```c
+//! GrsLinter *linter;
+//! GrsDiagnostics diagnostics;
+//! if (grs_linter_new("ALL", false, &linter) == GRS_STATUS_OK) {
+//!     if (grs_check(linter, (const uint8_t *)text, strlen(text), &diagnostics) == GRS_STATUS_OK) {
+//!         for (size_t i = 0; i < diagnostics.len; i++) { ... }
+//!         grs_diagnostics_free(&diagnostics);
+//!     }
+//!     grs_linter_free(linter);
+//! }
+//! ```
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+
+use grs::diagnostic::Severity;
+use grs::linter::RuleSet;
+use grs::registry::{Rule, rule_to_code};
+use grs::settings::Settings;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrsStatus {
+    Ok = 0,
+    /// A null pointer where one was not expected.
+    NullPointer,
+    InvalidUtf8,
+    UnknownRule,
+    /// A bug in grs.
+    Panic,
+}
+
+/// Severity of a diagnostic. Only errors should fail a check.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrsSeverity {
+    Info = 0,
+    Warning,
+    Error,
+}
+
+impl From<Severity> for GrsSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Info => Self::Info,
+            Severity::Warning => Self::Warning,
+            Severity::Error => Self::Error,
+        }
+    }
+}
+
+/// Maximum length of a rule code, including the terminating NUL.
+pub const GRS_CODE_SIZE: usize = 8;
+
+/// A linter: the rules to check. Opaque.
+pub struct GrsLinter {
+    rules: Vec<Rule>,
+    rule_set: RuleSet,
+}
+
+#[repr(C)]
+pub struct GrsDiagnostic {
+    /// NUL-terminated rule code, ex. `MDA`.
+    pub code: [c_char; GRS_CODE_SIZE],
+    /// Byte range of the diagnostic.
+    pub start: usize,
+    pub end: usize,
+    /// Byte range replaced by the fix. Only meaningful if there is a fix.
+    pub fix_start: usize,
+    pub fix_end: usize,
+    /// NUL-terminated replacement, or null if there is no fix.
+    pub replacement: *mut c_char,
+    pub severity: GrsSeverity,
+}
+
+#[repr(C)]
+pub struct GrsDiagnostics {
+    pub ptr: *mut GrsDiagnostic,
+    pub len: usize,
+}
+
+/// An UTF-8 buffer, not NUL-terminated.
+#[repr(C)]
+pub struct GrsString {
+    pub ptr: *mut u8,
+    pub len: usize,
+}
+
+fn guard(f: impl FnOnce() -> Result<(), GrsStatus>) -> GrsStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => GrsStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => GrsStatus::Panic,
+    }
+}
+
+unsafe fn text<'a>(ptr: *const u8, len: usize) -> Result<&'a str, GrsStatus> {
+    if len == 0 {
+        return Ok("");
+    }
+    if ptr.is_null() {
+        return Err(GrsStatus::NullPointer);
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    std::str::from_utf8(bytes).map_err(|_| GrsStatus::InvalidUtf8)
+}
+
+unsafe fn linter<'a>(linter: *const GrsLinter) -> Result<&'a GrsLinter, GrsStatus> {
+    unsafe { linter.as_ref() }.ok_or(GrsStatus::NullPointer)
+}
+
+/// The code of `rule`, truncated if needed: the last byte is always NUL.
+fn code(rule: Rule) -> [c_char; GRS_CODE_SIZE] {
+    let mut code = [0; GRS_CODE_SIZE];
+    let bytes = rule_to_code(rule).into_bytes();
+    for (dst, src) in code
+        .iter_mut()
+        .zip(bytes.into_iter().take(GRS_CODE_SIZE - 1))
+    {
+        *dst = src as c_char;
+    }
+    code
+}
+
+/// The version of grs, as a static NUL-terminated string.
+#[unsafe(no_mangle)]
+pub extern "C" fn grs_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// A static NUL-terminated description of `status`.
+#[unsafe(no_mangle)]
+pub extern "C" fn grs_status_message(status: GrsStatus) -> *const c_char {
+    let message: &CStr = match status {
+        GrsStatus::Ok => c"ok",
+        GrsStatus::NullPointer => c"unexpected null pointer",
+        GrsStatus::InvalidUtf8 => c"invalid UTF-8",
+        GrsStatus::UnknownRule => c"unknown rule code",
+        GrsStatus::Panic => c"internal error",
+    };
+    message.as_ptr()
+}
+
+/// Create a linter for `rules`, a NUL-terminated comma-separated list of rule
+/// codes (or `ALL`). The default rules are used if `rules` is null. Rules in
+/// preview are only used with `preview`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn grs_linter_new(
+    rules: *const c_char,
+    preview: bool,
+    out: *mut *mut GrsLinter,
+) -> GrsStatus {
+    guard(|| {
+        if out.is_null() {
+            return Err(GrsStatus::NullPointer);
+        }
+        let select = if rules.is_null() {
+            None
+        } else {
+            let rules = unsafe { CStr::from_ptr(rules) }
+                .to_str()
+                .map_err(|_| GrsStatus::InvalidUtf8)?;
+            let selectors = rules
+                .split(',')
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| GrsStatus::UnknownRule)?;
+            Some(selectors)
+        };
+        let settings = Settings {
+            select,
+            ignore: None,
+            preview: Some(preview),
+            severity: Vec::new(),
+            lang: None,
+        };
+        let rules = settings.rules();
+        let linter = Box::new(GrsLinter {
+            rule_set: RuleSet::new(&rules),
+            rules,
+        });
+        unsafe { *out = Box::into_raw(linter) };
+        Ok(())
+    })
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn grs_linter_free(linter: *mut GrsLinter) {
+    if !linter.is_null() {
+        drop(unsafe { Box::from_raw(linter) });
+    }
+}
+
+/// Check the UTF-8 buffer `text` of `len` bytes. On success, `out` must be
+/// released with `grs_diagnostics_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn grs_check(
+    linter: *const GrsLinter,
+    text: *const u8,
+    len: usize,
+    out: *mut GrsDiagnostics,
+) -> GrsStatus {
+    guard(|| {
+        let linter = unsafe { self::linter(linter) }?;
+        let text = unsafe { self::text(text, len) }?;
+        if out.is_null() {
+            return Err(GrsStatus::NullPointer);
+        }
+        let diagnostics: Box<[GrsDiagnostic]> = linter
+            .rule_set
+            .check(text)
+            .into_iter()
+            .map(|diagnostic| {
+                let (fix_start, fix_end, replacement) = match diagnostic.fix() {
+                    Some(fix) => {
+                        let replacement = CString::new(fix.replacement(text).replace('\0', ""))
+                            .expect("no NUL bytes");
+                        (
+                            fix.range().start(),
+                            fix.range().end(),
+                            replacement.into_raw(),
+                        )
+                    }
+                    None => (0, 0, ptr::null_mut()),
+                };
+                GrsDiagnostic {
+                    code: code(diagnostic.kind),
+                    start: diagnostic.range.start(),
+                    end: diagnostic.range.end(),
+                    fix_start,
+                    fix_end,
+                    replacement,
+                    severity: diagnostic.severity.into(),
+                }
+            })
+            .collect();
+        let len = diagnostics.len();
+        let ptr = Box::into_raw(diagnostics).cast::<GrsDiagnostic>();
+        unsafe { *out = GrsDiagnostics { ptr, len } };
+        Ok(())
+    })
+}
+
+/// Release the diagnostics returned by `grs_check`, and reset them to empty.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn grs_diagnostics_free(diagnostics: *mut GrsDiagnostics) {
+    let Some(diagnostics) = (unsafe { diagnostics.as_mut() }) else {
+        return;
+    };
+    if !diagnostics.ptr.is_null() {
+        let slice = ptr::slice_from_raw_parts_mut(diagnostics.ptr, diagnostics.len);
+        for diagnostic in unsafe { Box::from_raw(slice) } {
+            if !diagnostic.replacement.is_null() {
+                drop(unsafe { CString::from_raw(diagnostic.replacement) });
+            }
+        }
+    }
+    diagnostics.ptr = ptr::null_mut();
+    diagnostics.len = 0;
+}
+
+/// Fix the UTF-8 buffer `text` of `len` bytes. On success, `out` must be
+/// released with `grs_string_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn grs_fix(
+    linter: *const GrsLinter,
+    text: *const u8,
+    len: usize,
+    out: *mut GrsString,
+) -> GrsStatus {
+    guard(|| {
+        let linter = unsafe { self::linter(linter) }?;
+        let text = unsafe { self::text(text, len) }?;
+        if out.is_null() {
+            return Err(GrsStatus::NullPointer);
+        }
+        let fixed = grs::linter::fix(text, &linter.rules).text;
+        let fixed = fixed.into_bytes().into_boxed_slice();
+        let len = fixed.len();
+        let ptr = Box::into_raw(fixed).cast::<u8>();
+        unsafe { *out = GrsString { ptr, len } };
+        Ok(())
+    })
+}
+
+/// Release a string returned by `grs_fix`, and reset it to empty.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn grs_string_free(string: *mut GrsString) {
+    let Some(string) = (unsafe { string.as_mut() }) else {
+        return;
+    };
+    if !string.ptr.is_null() {
+        let slice = ptr::slice_from_raw_parts_mut(string.ptr, string.len);
+        drop(unsafe { Box::from_raw(slice) });
+    }
+    string.ptr = ptr::null_mut();
+    string.len = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grs::registry::{Group, LintRule, Phase, RuleMeta};
+
+    fn new_linter(rules: &CStr) -> *mut GrsLinter {
+        let mut linter = ptr::null_mut();
+        assert_eq!(
+            unsafe { grs_linter_new(rules.as_ptr(), false, &raw mut linter) },
+            GrsStatus::Ok
+        );
+        linter
+    }
+
+    #[test]
+    fn test_check() {
+        let linter = new_linter(c"RFN, MS");
+        let text = "Να μην την δώσεις.";
+        let mut diagnostics = GrsDiagnostics {
+            ptr: ptr::null_mut(),
+            len: 0,
+        };
+        let status = unsafe { grs_check(linter, text.as_ptr(), text.len(), &raw mut diagnostics) };
+        assert_eq!(status, GrsStatus::Ok);
+        assert_eq!(diagnostics.len, 1);
+
+        let diagnostic = unsafe { &*diagnostics.ptr };
+        let code = unsafe { CStr::from_ptr(diagnostic.code.as_ptr()) };
+        assert_eq!(code, c"RFN");
+        assert_eq!(&text[diagnostic.start..diagnostic.end], "την");
+        // Only the final ν is removed.
+        assert_eq!(&text[diagnostic.fix_start..diagnostic.fix_end], "ν");
+        let replacement = unsafe { CStr::from_ptr(diagnostic.replacement) };
+        assert_eq!(replacement, c"");
+
+        unsafe { grs_diagnostics_free(&raw mut diagnostics) };
+        assert!(diagnostics.ptr.is_null());
+        unsafe { grs_linter_free(linter) };
+    }
+
+    #[test]
+    fn test_fix() {
+        let linter = new_linter(c"ALL");
+        let text = "Να μην την δώσεις.";
+        let mut fixed = GrsString {
+            ptr: ptr::null_mut(),
+            len: 0,
+        };
+        let status = unsafe { grs_fix(linter, text.as_ptr(), text.len(), &raw mut fixed) };
+        assert_eq!(status, GrsStatus::Ok);
+        let bytes = unsafe { std::slice::from_raw_parts(fixed.ptr, fixed.len) };
+        assert_eq!(std::str::from_utf8(bytes), Ok("Να μην τη δώσεις."));
+        unsafe { grs_string_free(&raw mut fixed) };
+        unsafe { grs_linter_free(linter) };
+    }
+
+    #[test]
+    fn test_preview() {
+        let linter = new_linter(c"MDAN");
+        assert!(unsafe { &*linter }.rules.is_empty());
+        unsafe { grs_linter_free(linter) };
+
+        let mut linter = ptr::null_mut();
+        let status = unsafe { grs_linter_new(c"MDAN".as_ptr(), true, &raw mut linter) };
+        assert_eq!(status, GrsStatus::Ok);
+        assert_eq!(
+            unsafe { &*linter }.rules,
+            [Rule::MissingDoubleAccentsNumeral]
+        );
+        unsafe { grs_linter_free(linter) };
+    }
+
+    #[test]
+    fn test_header() {
+        // Run with GRS_FFI_WRITE_HEADER=1 to update the header.
+        assert_eq!(
+            include_str!(concat!(env!("OUT_DIR"), "/grs.h")),
+            include_str!("../include/grs.h")
+        );
+    }
+
+    #[test]
+    fn test_long_code() {
+        struct Long;
+        impl LintRule for Long {
+            fn meta(&self) -> &'static RuleMeta {
+                &RuleMeta {
+                    code: "LONGCODES",
+                    name: "Long",
+                    phase: Phase::Raw,
+                    group: Group::Typography,
+                    fixable: false,
+                    default: false,
+                    preview: false,
+                    severity: Severity::Info,
+                    docs: "A custom rule with a long code.",
+                }
+            }
+        }
+
+        let long = code(Rule::new(&Long));
+        assert_eq!(unsafe { CStr::from_ptr(long.as_ptr()) }, c"LONGCOD");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut linter = ptr::null_mut();
+        let status = unsafe { grs_linter_new(c"XYZ".as_ptr(), false, &raw mut linter) };
+        assert_eq!(status, GrsStatus::UnknownRule);
+        assert!(linter.is_null());
+
+        let linter = new_linter(c"ALL");
+        let mut fixed = GrsString {
+            ptr: ptr::null_mut(),
+            len: 0,
+        };
+        let invalid = [0xff, 0xfe];
+        let status = unsafe { grs_fix(linter, invalid.as_ptr(), 2, &raw mut fixed) };
+        assert_eq!(status, GrsStatus::InvalidUtf8);
+        let status = unsafe { grs_fix(ptr::null(), invalid.as_ptr(), 2, &raw mut fixed) };
+        assert_eq!(status, GrsStatus::NullPointer);
+        unsafe { grs_linter_free(linter) };
+    }
+}
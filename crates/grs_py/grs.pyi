@@ -0,0 +1,47 @@
+__version__: str
+
+class Rule:
+    code: str
+    name: str
+    fixable: bool
+    default: bool
+    group: str
+    severity: str
+
+class Fix:
+    start: int
+    end: int
+    replacement: str
+
+class Suggestion:
+    label: str
+    fix: Fix
+    preferred: bool
+
+class Diagnostic:
+    code: str
+    start: int
+    end: int
+    fix: Fix | None
+    suggestions: list[Suggestion]
+    severity: str
+    message: str
+
+class Token:
+    kind: str
+    text: str
+    start: int
+    end: int
+
+def rules() -> list[Rule]: ...
+def check(
+    text: str,
+    rules: list[str] | None = None,
+    lang: str = "en",
+    preview: bool = False,
+) -> list[Diagnostic]: ...
+def fix(
+    text: str, rules: list[str] | None = None, preview: bool = False
+) -> str: ...
+def tokenize(text: str) -> list[Token]: ...
+def to_monotonic(text: str) -> str: ...
@@ -0,0 +1,274 @@
+//! Python bindings.
+//!
+//! Ranges are given in code points, so that they can be used to slice Python
+//! strings. The GIL is released while linting.
+#![allow(clippy::needless_pass_by_value)]
+
+use grs::diagnostic::{self, Lang};
+use grs::registry::{Rule, rule_to_code, rule_to_name};
+use grs::settings::Settings;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Conversion from byte offsets to code point indices.
+struct CharIndex {
+    boundaries: Vec<usize>,
+}
+
+impl CharIndex {
+    fn new(text: &str) -> Self {
+        Self {
+            boundaries: text.char_indices().map(|(idx, _)| idx).collect(),
+        }
+    }
+
+    fn get(&self, offset: usize) -> usize {
+        self.boundaries.partition_point(|&idx| idx < offset)
+    }
+}
+
+#[pyclass(name = "Rule", frozen, get_all)]
+struct PyRule {
+    code: String,
+    name: String,
+    fixable: bool,
+    /// Whether the rule is checked when no rules are given.
+    default: bool,
+    /// Group of the rule, ex. "accent".
+    group: String,
+    /// Default severity of the rule: "info", "warning" or "error".
+    severity: String,
+}
+
+#[pymethods]
+impl PyRule {
+    fn __repr__(&self) -> String {
+        format!("Rule({}, {})", self.code, self.name)
+    }
+}
+
+impl From<Rule> for PyRule {
+    fn from(rule: Rule) -> Self {
+        Self {
+            code: rule_to_code(rule),
+            name: rule_to_name(rule).to_string(),
+            fixable: rule.has_fix(),
+            default: rule.is_default(),
+            group: rule.group().to_string(),
+            severity: rule.severity().to_string(),
+        }
+    }
+}
+
+#[pyclass(name = "Fix", frozen, get_all)]
+struct PyFix {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+#[pymethods]
+impl PyFix {
+    fn __repr__(&self) -> String {
+        format!("Fix({}..{}, {:?})", self.start, self.end, self.replacement)
+    }
+}
+
+#[pyclass(name = "Suggestion", frozen, get_all)]
+struct PySuggestion {
+    label: String,
+    fix: Py<PyFix>,
+    /// Whether `fix` applies it.
+    preferred: bool,
+}
+
+#[pymethods]
+impl PySuggestion {
+    fn __repr__(&self) -> String {
+        format!("Suggestion({:?})", self.label)
+    }
+}
+
+#[pyclass(name = "Diagnostic", frozen, get_all)]
+struct PyDiagnostic {
+    /// Rule code, ex. `MDA`.
+    code: String,
+    start: usize,
+    end: usize,
+    /// The fix of the preferred suggestion, if any.
+    fix: Option<Py<PyFix>>,
+    suggestions: Vec<Py<PySuggestion>>,
+    severity: String,
+    /// What is wrong, in the language asked for.
+    message: String,
+}
+
+#[pymethods]
+impl PyDiagnostic {
+    fn __repr__(&self) -> String {
+        format!("Diagnostic({}, {}..{})", self.code, self.start, self.end)
+    }
+}
+
+#[pyclass(name = "Token", frozen, get_all)]
+struct PyToken {
+    kind: String,
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+#[pymethods]
+impl PyToken {
+    fn __repr__(&self) -> String {
+        format!("Token({}, {:?})", self.kind, self.text)
+    }
+}
+
+/// Rules from codes, code prefixes, groups (or `ALL`), the default ones if `None`.
+/// Rules in preview are only used with `preview`.
+fn load_config(rules: Option<Vec<String>>, preview: bool) -> PyResult<Vec<Rule>> {
+    let select = rules
+        .map(|codes| codes.iter().map(|code| code.parse()).collect())
+        .transpose()
+        .map_err(PyValueError::new_err)?;
+    let settings = Settings {
+        select,
+        ignore: None,
+        preview: Some(preview),
+        severity: Vec::new(),
+        lang: None,
+    };
+    Ok(settings.rules())
+}
+
+/// All the rules.
+#[pyfunction]
+fn rules() -> Vec<PyRule> {
+    Rule::iter().map(PyRule::from).collect()
+}
+
+/// Diagnostics of `text` for the given rule codes, with messages in `lang`
+/// (`en` or `el`).
+#[pyfunction]
+#[pyo3(signature = (text, rules=None, lang="en", preview=false))]
+fn check(
+    py: Python<'_>,
+    text: &str,
+    rules: Option<Vec<String>>,
+    lang: &str,
+    preview: bool,
+) -> PyResult<Vec<PyDiagnostic>> {
+    let lang: Lang = lang.parse().map_err(PyValueError::new_err)?;
+    let config = load_config(rules, preview)?;
+    let diagnostics = py.allow_threads(|| grs::linter::check(text, &config));
+    let index = CharIndex::new(text);
+    diagnostics
+        .into_iter()
+        .map(
+            |diagnostic::Diagnostic {
+                 kind,
+                 range,
+                 suggestions,
+                 severity,
+                 message,
+             }| {
+                let mut fix = None;
+                let suggestions = suggestions
+                    .into_iter()
+                    .map(|suggestion| {
+                        let py_fix = Py::new(
+                            py,
+                            PyFix {
+                                start: index.get(suggestion.fix.range().start()),
+                                end: index.get(suggestion.fix.range().end()),
+                                replacement: suggestion.fix.replacement(text),
+                            },
+                        )?;
+                        if suggestion.preferred && fix.is_none() {
+                            fix = Some(py_fix.clone_ref(py));
+                        }
+                        Py::new(
+                            py,
+                            PySuggestion {
+                                label: suggestion.label.get(lang).to_string(),
+                                fix: py_fix,
+                                preferred: suggestion.preferred,
+                            },
+                        )
+                    })
+                    .collect::<PyResult<_>>()?;
+                Ok(PyDiagnostic {
+                    code: rule_to_code(kind),
+                    start: index.get(range.start()),
+                    end: index.get(range.end()),
+                    fix,
+                    suggestions,
+                    severity: severity.to_string(),
+                    message: message.get(lang).to_string(),
+                })
+            },
+        )
+        .collect()
+}
+
+/// `text` with every fixable diagnostic fixed.
+#[pyfunction]
+#[pyo3(signature = (text, rules=None, preview=false))]
+fn fix(py: Python<'_>, text: &str, rules: Option<Vec<String>>, preview: bool) -> PyResult<String> {
+    let config = load_config(rules, preview)?;
+    let fixed = py.allow_threads(|| grs::linter::fix(text, &config).text);
+    Ok(fixed)
+}
+
+#[pyfunction]
+fn tokenize(text: &str) -> Vec<PyToken> {
+    let index = CharIndex::new(text);
+    grs::tokenizer::tokenize(text)
+        .iter()
+        .map(|token| PyToken {
+            kind: format!("{:?}", token.kind()),
+            text: token.text().to_string(),
+            start: index.get(token.range().start()),
+            end: index.get(token.range().end()),
+        })
+        .collect()
+}
+
+// Grac bindings
+
+#[pyfunction]
+fn to_monotonic(text: &str) -> String {
+    grac::to_monotonic(text)
+}
+
+#[pymodule]
+#[pyo3(name = "grs")]
+fn grs_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    module.add_class::<PyRule>()?;
+    module.add_class::<PyFix>()?;
+    module.add_class::<PySuggestion>()?;
+    module.add_class::<PyDiagnostic>()?;
+    module.add_class::<PyToken>()?;
+    module.add_function(wrap_pyfunction!(rules, module)?)?;
+    module.add_function(wrap_pyfunction!(check, module)?)?;
+    module.add_function(wrap_pyfunction!(fix, module)?)?;
+    module.add_function(wrap_pyfunction!(tokenize, module)?)?;
+    module.add_function(wrap_pyfunction!(to_monotonic, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_index() {
+        let text = "😀 Καλημέρα";
+        let index = CharIndex::new(text);
+        assert_eq!(index.get(0), 0);
+        assert_eq!(index.get(5), 2);
+        assert_eq!(index.get(text.len()), text.chars().count());
+    }
+}
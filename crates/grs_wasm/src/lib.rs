@@ -1,11 +1,12 @@
 #![allow(clippy::missing_errors_doc)]
 
-use grs::diagnostic::{Diagnostic, Fix};
+use grs::cli::RuleSelector;
+use grs::diagnostic::{Diagnostic, Lang, Severities, Severity};
 use grs::registry::{Rule, code_to_rule, rule_to_code, rule_to_name};
+use grs::settings::Settings;
 use grs::tokenizer::Token;
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
-use strum::IntoEnumIterator;
 use wasm_bindgen::JsValue;
 use wasm_bindgen::prelude::*;
 use web_sys::js_sys::Error;
@@ -19,6 +20,19 @@ export interface Diagnostic {
     end: number;
   };
   fix: string;
+  severity: "info" | "warning" | "error";
+  message: string;
+  suggestions: Suggestion[];
+};
+
+export interface Suggestion {
+  label: string;
+  range: {
+    start: number;
+    end: number;
+  };
+  replacement: string;
+  preferred: boolean;
 };
 
 export interface Token {
@@ -59,9 +73,20 @@ fn byte_range_to_char_range(text: &str, byte_range: Range<usize>) -> Range<usize
     }
 }
 
-fn to_fixjs(fix: Option<&Fix>) -> Option<String> {
+/// The text of the diagnostic range once fixed.
+fn to_fixjs(text: &str, diagnostic: &Diagnostic) -> Option<String> {
+    let fix = diagnostic.fix()?;
+    let range = fix.range();
+    let start = diagnostic.range.start().min(range.start());
+    let end = diagnostic.range.end().max(range.end());
+    let fixed = format!(
+        "{}{}{}",
+        &text[start..range.start()],
+        fix.replacement(text),
+        &text[range.end()..end]
+    );
     // The trim is hacky...
-    fix.as_ref().map(|fix| fix.replacement.trim().to_string())
+    Some(fixed.trim().to_string())
 }
 
 fn pascal_to_snake(s: &str) -> String {
@@ -85,10 +110,22 @@ pub struct DiagnosticJs {
     pub range: Range<usize>,
     // Only the replacement at the moment.
     pub fix: Option<String>,
+    pub severity: String,
+    pub message: String,
+    /// Every suggestion, including the preferred one of `fix`.
+    pub suggestions: Vec<SuggestionJs>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuggestionJs {
+    pub label: String,
+    pub range: Range<usize>,
+    pub replacement: String,
+    pub preferred: bool,
 }
 
 impl DiagnosticJs {
-    fn new(text: &str, diagnostic: &Diagnostic) -> Self {
+    fn new(text: &str, diagnostic: &Diagnostic, lang: Lang) -> Self {
         let byte_range = diagnostic.range.start()..diagnostic.range.end();
         let range = byte_range_to_char_range(text, byte_range);
         let pascal_case_rule = format!("{:?}", diagnostic.kind);
@@ -97,7 +134,22 @@ impl DiagnosticJs {
         Self {
             kind,
             range,
-            fix: to_fixjs(diagnostic.fix.as_ref()),
+            fix: to_fixjs(text, diagnostic),
+            severity: diagnostic.severity.to_string(),
+            message: diagnostic.message.get(lang).to_string(),
+            suggestions: diagnostic
+                .suggestions
+                .iter()
+                .map(|suggestion| SuggestionJs {
+                    label: suggestion.label.get(lang).to_string(),
+                    range: byte_range_to_char_range(
+                        text,
+                        suggestion.fix.range().start()..suggestion.fix.range().end(),
+                    ),
+                    replacement: suggestion.fix.replacement(text),
+                    preferred: suggestion.preferred,
+                })
+                .collect(),
         }
     }
 }
@@ -110,22 +162,94 @@ fn all_rules() -> Vec<Rule> {
 struct RuleJs {
     code: String,
     name: String,
+    #[serde(default)]
+    group: String,
+    /// Overrides the default severity of the rule, if set.
+    #[serde(default)]
+    severity: String,
     active: bool,
 }
 
-fn load_config(options: JsValue) -> Vec<Rule> {
-    if options.is_null() || options.is_undefined() {
-        all_rules()
-    } else {
-        // options is expected to be:
-        // { code: 'MDA', name: 'MissingDoubleAccents', active: true }
-        let rules_js: Vec<RuleJs> = serde_wasm_bindgen::from_value(options).unwrap_or_default();
+/// Options of the functions taking a config.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OptionsJs {
+    /// The states of the rules, as returned by `rules`.
+    Rules(Vec<RuleJs>),
+    /// Selectors, as in a `grs.toml`: codes, code prefixes, groups or ALL.
+    Selection {
+        select: Option<Vec<String>>,
+        ignore: Option<Vec<String>>,
+        preview: Option<bool>,
+        info: Option<Vec<String>>,
+        warn: Option<Vec<String>>,
+        error: Option<Vec<String>>,
+    },
+}
 
-        rules_js
-            .into_iter()
-            .filter(|opt| opt.active)
-            .filter_map(|opt| code_to_rule(&opt.code))
+fn parse_selectors(selectors: Option<Vec<String>>) -> Option<Vec<RuleSelector>> {
+    selectors.map(|selectors| {
+        selectors
+            .iter()
+            .filter_map(|selector| selector.parse().ok())
             .collect()
+    })
+}
+
+fn load_config(options: JsValue) -> (Vec<Rule>, Severities) {
+    if options.is_null() || options.is_undefined() {
+        let rules = all_rules()
+            .into_iter()
+            .filter(|rule| !rule.is_preview())
+            .collect();
+        return (rules, Severities::default());
+    }
+    // options is expected to be either:
+    // [{ code: 'MDA', name: 'MissingDoubleAccents', group: 'accent', severity: 'error', active: true }]
+    // { select: ['accent', 'P'], ignore: ['MNA'], preview: true, warn: ['DW'] }
+    match serde_wasm_bindgen::from_value(options) {
+        Ok(OptionsJs::Rules(rules_js)) => {
+            let mut severities = Severities::default();
+            let rules = rules_js
+                .into_iter()
+                .filter(|opt| opt.active)
+                .filter_map(|opt| {
+                    let rule = code_to_rule(&opt.code)?;
+                    if let Ok(severity) = opt.severity.parse() {
+                        severities.set(rule, severity);
+                    }
+                    Some(rule)
+                })
+                .collect();
+            (rules, severities)
+        }
+        Ok(OptionsJs::Selection {
+            select,
+            ignore,
+            preview,
+            info,
+            warn,
+            error,
+        }) => {
+            let mut settings = Settings {
+                select: parse_selectors(select),
+                ignore: parse_selectors(ignore),
+                preview,
+                severity: Vec::new(),
+                lang: None,
+            };
+            for (selectors, severity) in [
+                (info, Severity::Info),
+                (warn, Severity::Warning),
+                (error, Severity::Error),
+            ] {
+                if let Some(selectors) = parse_selectors(selectors) {
+                    settings.set_severity(selectors, severity);
+                }
+            }
+            (settings.rules(), settings.severities())
+        }
+        Err(_) => (Vec::new(), Severities::default()),
     }
 }
 
@@ -136,6 +260,8 @@ pub fn rules() -> Result<JsValue, Error> {
         .map(|&rule| RuleJs {
             code: rule_to_code(rule),
             name: rule_to_name(rule).to_string(),
+            group: rule.group().to_string(),
+            severity: rule.severity().to_string(),
             active: false,
         })
         .collect();
@@ -144,21 +270,28 @@ pub fn rules() -> Result<JsValue, Error> {
 
 // Reference:
 // https://rustwasm.github.io/docs/wasm-bindgen/reference/arbitrary-data-with-serde.html
+//
+// Messages are in `lang`, "en" or "el", defaulting to English.
 #[wasm_bindgen]
-pub fn scan_text(text: &str, options: JsValue) -> Result<JsValue, Error> {
-    let config = load_config(options);
-    let diagnostics_js = grs::linter::check(text, config.as_slice())
+pub fn scan_text(text: &str, options: JsValue, lang: Option<String>) -> Result<JsValue, Error> {
+    let lang = match lang {
+        Some(code) => code.parse().map_err(|err: String| Error::new(&err))?,
+        None => Lang::default(),
+    };
+    let (config, severities) = load_config(options);
+    let mut diagnostics = grs::linter::check(text, config.as_slice());
+    severities.apply(&mut diagnostics);
+    let diagnostics_js = diagnostics
         .iter()
-        .map(|diagnostic| DiagnosticJs::new(text, diagnostic))
+        .map(|diagnostic| DiagnosticJs::new(text, diagnostic, lang))
         .collect::<Vec<_>>();
     serde_wasm_bindgen::to_value(&diagnostics_js).map_err(into_error)
 }
 
 #[wasm_bindgen]
 pub fn fix(text: &str, options: JsValue) -> String {
-    let config = load_config(options);
-    let (res, _, _) = grs::linter::fix(text, &config);
-    res
+    let (config, _) = load_config(options);
+    grs::linter::fix(text, &config).text
 }
 
 #[derive(Debug, Serialize, Deserialize)]
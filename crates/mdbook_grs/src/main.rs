@@ -0,0 +1,302 @@
+//! mdBook preprocessor linting every chapter with grs.
+//!
+//! Diagnostics are printed to stderr with the path of their chapter. Sources
+//! are never modified: fixes, if enabled, only apply to the rendered book.
+//!
+//! ```toml
+//! [preprocessor.grs]
+//! select = ["ALL"]     # Defaults to the grs.toml of the book, if any.
+//! ignore = ["DW"]
+//! preview = true       # Use the rules in preview.
+//! warn = ["FC"]        # Override the severity of rules (info, warn, error).
+//! lang = "el"          # Language of the messages (en, el).
+//! fail-on-error = true # Fail the build if there are diagnostics of error severity.
+//! fix = true           # Render the book with the safe fixes applied.
+//! ```
+//!
+//! <https://rust-lang.github.io/mdBook/for_developers/preprocessors.html>
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use serde_json::Value;
+
+use grs::cli::RuleSelector;
+use grs::diagnostic::{ReportOptions, Severity};
+use grs::formats::{self, markdown::Markdown};
+use grs::linter::Counter;
+use grs::registry::Rule;
+use grs::settings::Settings;
+
+#[derive(Debug, Parser)]
+#[command(name = "mdbook-grs", about = "mdBook preprocessor for grs", version)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check whether a renderer is supported
+    Supports { renderer: String },
+}
+
+/// The `[preprocessor.grs]` table of `book.toml`.
+#[derive(Debug, Default)]
+struct Options {
+    settings: Settings,
+    fail_on_error: bool,
+    fix: bool,
+}
+
+fn parse_selectors(key: &str, value: &Value) -> Result<Vec<RuleSelector>, String> {
+    let invalid = || format!("`{key}` must be a list of rule codes");
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|code| code.as_str().ok_or_else(invalid)?.parse())
+        .collect()
+}
+
+impl Options {
+    fn from_json(table: &Value) -> Result<Self, String> {
+        let mut options = Self::default();
+        let Some(table) = table.as_object() else {
+            return Ok(options);
+        };
+        let flag = |key: &str, value: &Value| {
+            value
+                .as_bool()
+                .ok_or_else(|| format!("`{key}` must be a boolean"))
+        };
+        for (key, value) in table {
+            match key.as_str() {
+                "select" => options.settings.select = Some(parse_selectors(key, value)?),
+                "ignore" => options.settings.ignore = Some(parse_selectors(key, value)?),
+                "preview" => options.settings.preview = Some(flag(key, value)?),
+                "info" => options
+                    .settings
+                    .set_severity(parse_selectors(key, value)?, Severity::Info),
+                "warn" => options
+                    .settings
+                    .set_severity(parse_selectors(key, value)?, Severity::Warning),
+                "error" => options
+                    .settings
+                    .set_severity(parse_selectors(key, value)?, Severity::Error),
+                "lang" => {
+                    let code = value.as_str();
+                    options.settings.lang = Some(
+                        code.ok_or_else(|| format!("`{key}` must be a string"))?
+                            .parse()?,
+                    );
+                }
+                "fail-on-error" => options.fail_on_error = flag(key, value)?,
+                "fix" => options.fix = flag(key, value)?,
+                // Keys of mdBook itself.
+                "command" | "renderers" | "before" | "after" | "optional" => {}
+                _ => return Err(format!("unknown key `{key}`")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Lint a chapter, and fix its content if asked to. Returns the number of
+/// diagnostics per rule.
+fn process_chapter(
+    chapter: &mut serde_json::Map<String, Value>,
+    src: &Path,
+    rules: &[Rule],
+    report: &ReportOptions,
+    fix: bool,
+) -> Result<Counter, String> {
+    let Some(content) = chapter.get("content").and_then(Value::as_str) else {
+        return Ok(Counter::new());
+    };
+    let path = chapter
+        .get("source_path")
+        .or_else(|| chapter.get("path"))
+        .and_then(Value::as_str)
+        .map_or_else(
+            || chapter["name"].as_str().unwrap_or_default().to_string(),
+            |path| src.join(path).display().to_string(),
+        );
+
+    let (messages, counter) = formats::lint_only(&Markdown, content, rules, report)
+        .map_err(|err| format!("{path}: {err}"))?;
+    for message in &messages {
+        eprintln!("{path}:{message}");
+    }
+    if fix {
+        let fixed =
+            formats::fix(&Markdown, content, rules).map_err(|err| format!("{path}: {err}"))?;
+        if let Some(err) = &fixed.error {
+            eprintln!("Warning: {path}: {err}");
+        }
+        chapter.insert("content".to_string(), Value::String(fixed.text));
+    }
+    Ok(counter)
+}
+
+/// Visit every chapter of the book, including nested ones.
+fn process_items(
+    value: &mut Value,
+    src: &Path,
+    rules: &[Rule],
+    report: &ReportOptions,
+    fix: bool,
+    statistics: &mut Counter,
+) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Object(chapter)) = map.get_mut("Chapter") {
+                let counter = process_chapter(chapter, src, rules, report, fix)?;
+                for (rule, count) in counter {
+                    *statistics.entry(rule).or_insert(0) += count;
+                }
+            }
+            for item in map.values_mut() {
+                process_items(item, src, rules, report, fix, statistics)?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                process_items(item, src, rules, report, fix, statistics)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Process the `[context, book]` sent by mdBook, returning the book.
+fn preprocess(input: &str) -> Result<Value, String> {
+    let input: Value = serde_json::from_str(input).map_err(|err| err.to_string())?;
+    let Value::Array(mut input) = input else {
+        return Err("expected [context, book]".to_string());
+    };
+    if input.len() != 2 {
+        return Err("expected [context, book]".to_string());
+    }
+    let mut book = input.pop().unwrap_or_default();
+    let context = input.pop().unwrap_or_default();
+
+    let root = Path::new(context["root"].as_str().unwrap_or("."));
+    let src = Path::new(context["config"]["book"]["src"].as_str().unwrap_or("src"));
+    let options = Options::from_json(&context["config"]["preprocessor"]["grs"])?;
+    let settings = options.settings.or(Settings::discover(root)?);
+    for warning in settings.warnings() {
+        eprintln!("Warning: {warning}");
+    }
+    let rules = settings.rules();
+    let report = settings.report_options();
+
+    let mut statistics = Counter::new();
+    process_items(
+        &mut book,
+        src,
+        &rules,
+        &report,
+        options.fix,
+        &mut statistics,
+    )?;
+    let count = statistics.values().sum::<usize>();
+    let errors = statistics
+        .iter()
+        .filter(|(rule, _)| report.severities.get(**rule) == Severity::Error)
+        .map(|(_, count)| count)
+        .sum::<usize>();
+    if count > 0 {
+        eprintln!("grs: found {count} problems, {errors} of error severity.");
+        if options.fail_on_error && errors > 0 {
+            return Err("failing the build (`fail-on-error = true`)".to_string());
+        }
+    }
+    Ok(book)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if let Some(Command::Supports { renderer: _ }) = args.command {
+        // Only the Markdown sources are touched: any renderer is fine.
+        return ExitCode::SUCCESS;
+    }
+
+    let mut input = String::new();
+    let result = std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|err| err.to_string())
+        .and_then(|_| preprocess(&input));
+    match result {
+        Ok(book) => {
+            println!("{book}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("grs: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(preprocessor: &str) -> String {
+        format!(
+            r##"[
+                {{"root": "/nonexistent", "renderer": "html", "mdbook_version": "0.4.48",
+                  "config": {{"book": {{"src": "src"}}, "preprocessor": {{"grs": {preprocessor}}}}}}},
+                {{"sections": [
+                    {{"Chapter": {{"name": "Α", "content": "Να μην την δώσεις.",
+                      "path": "a.md", "source_path": "a.md", "sub_items": [
+                        {{"Chapter": {{"name": "Β", "content": "# Τίτλος\n\nΚαι την πόλη.",
+                          "path": "b.md", "source_path": "b.md", "sub_items": []}}}}
+                    ]}}}},
+                    "Separator"
+                ], "__non_exhaustive": null}}
+            ]"##
+        )
+    }
+
+    fn contents(book: &Value) -> Vec<&str> {
+        let a = &book["sections"][0]["Chapter"];
+        let b = &a["sub_items"][0]["Chapter"];
+        vec![
+            a["content"].as_str().unwrap(),
+            b["content"].as_str().unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_fix() {
+        let book = preprocess(&input(r#"{"select": ["RFN"], "fix": true}"#)).unwrap();
+        assert_eq!(
+            contents(&book),
+            ["Να μην τη δώσεις.", "# Τίτλος\n\nΚαι την πόλη."]
+        );
+    }
+
+    #[test]
+    fn test_untouched() {
+        let book = preprocess(&input(r#"{"select": ["RFN"]}"#)).unwrap();
+        assert_eq!(contents(&book)[0], "Να μην την δώσεις.");
+    }
+
+    #[test]
+    fn test_fail_on_error() {
+        assert!(
+            preprocess(&input(
+                r#"{"select": ["RFN"], "error": ["RFN"], "fail-on-error": true}"#
+            ))
+            .is_err()
+        );
+        // Warnings do not fail the build.
+        assert!(preprocess(&input(r#"{"select": ["RFN"], "fail-on-error": true}"#)).is_ok());
+        assert!(preprocess(&input(r#"{"select": ["DW"], "fail-on-error": true}"#)).is_ok());
+        assert!(preprocess(&input(r#"{"unknown": 1}"#)).is_err());
+    }
+}